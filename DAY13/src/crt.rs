@@ -0,0 +1,97 @@
+/// Reasons why a system of congruences has no answer we can give
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+enum CrtError {
+    /// Two congruences contradict each other
+    Inconsistent,
+    /// A modulus is zero or negative
+    BadModulus,
+    /// The period of the answer does not fit in an `i128`
+    Overflow
+}
+
+/// `x ≡ residue (mod modulus)`, with `0 <= residue < modulus`
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+struct Congruence {
+    residue: i128,
+    modulus: i128
+}
+
+/// Returns `(g, p, q)` such that `g = gcd(a, b) = a*p + b*q`
+fn ext_gcd(a: i128, b: i128) -> (i128, i128, i128) {
+    let (mut old_r, mut r) = (a, b);
+    let (mut old_p, mut p) = (1, 0);
+    let (mut old_q, mut q) = (0, 1);
+    while r != 0 {
+        let k = old_r / r;
+        (old_r, r) = (r, old_r - k*r);
+        (old_p, p) = (p, old_p - k*p);
+        (old_q, q) = (q, old_q - k*q);
+    }
+    (old_r, old_p, old_q)
+}
+
+/// `a*b mod m` for `0 <= a, b < m`, even when `a*b` does not fit
+fn mul_mod(a: i128, b: i128, m: i128) -> i128 {
+    if let Some(p) = a.checked_mul(b) {
+        return p % m;
+    }
+    // Double and add, which only needs 2*m to fit in an u128
+    let (mut a, mut b, m) = (a as u128, b as u128, m as u128);
+    let mut res = 0;
+    while b > 0 {
+        if b & 1 == 1 {
+            res = (res + a) % m;
+        }
+        a = (a + a) % m;
+        b >>= 1;
+    }
+    res as i128
+}
+
+impl Congruence {
+    /// Every integer, which is where a system starts
+    const ANY: Congruence = Congruence { residue: 0, modulus: 1 };
+
+    /// # Errors
+    ///
+    /// Returns `CrtError::BadModulus` when `modulus` is not positive
+    fn new(residue: i128, modulus: i128) -> Result<Self, CrtError> {
+        if modulus <= 0 {
+            return Err(CrtError::BadModulus);
+        }
+        Ok(Congruence { residue: residue.rem_euclid(modulus), modulus })
+    }
+
+    /// The congruence met by the integers meeting both `self` and
+    /// `other`, whose modulus is the lcm of theirs
+    ///
+    /// Moduli need not be coprime: with `g` their gcd, the residues must
+    /// then agree modulo `g`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `CrtError::Inconsistent` when no integer meets both, and
+    /// `CrtError::Overflow` when the lcm does not fit in an `i128`
+    fn combine(self, other: Congruence) -> Result<Congruence, CrtError> {
+        let (g, p, _) = ext_gcd(self.modulus, other.modulus);
+        let diff = other.residue - self.residue;
+        if diff % g != 0 {
+            return Err(CrtError::Inconsistent);
+        }
+        let step = other.modulus / g;
+        let modulus = (self.modulus / g).checked_mul(other.modulus).ok_or(CrtError::Overflow)?;
+        // self.residue + self.modulus*k, where self.modulus*k ≡ diff (mod other.modulus)
+        let k = mul_mod((diff / g).rem_euclid(step), p.rem_euclid(step), step);
+        Ok(Congruence { residue: self.residue + self.modulus * k, modulus })
+    }
+}
+
+/// Solve a system of congruences one at a time, which works on any
+/// iterator, however long
+///
+/// # Errors
+///
+/// Returns the first [CrtError](CrtError) met
+fn crt(congruences: impl IntoIterator<Item=Congruence>) -> Result<Congruence, CrtError> {
+    congruences.into_iter().try_fold(Congruence::ANY, Congruence::combine)
+}
@@ -1,5 +1,9 @@
 use std::fs::File;
 use std::io::prelude::*;
+use std::convert::TryFrom;
+
+include!("crt.rs");
+include!("schedule.rs");
 
 fn read_data(filepath: &str) -> std::io::Result<String> {
     let mut file = File::open(filepath)?;
@@ -11,52 +15,83 @@ fn read_data(filepath: &str) -> std::io::Result<String> {
 /// # Errors
 ///
 /// Returns () as error for lack of a better type
-fn sol1(data: &str) -> Result<usize, ()> {
-    let mut datasplit = data.split('\n');
-    let start_time = datasplit.next().unwrap().parse::<usize>().unwrap();
-    let mut busline = datasplit.next().unwrap().split(',')
-        // Parse correct bus lines
-        .filter_map(|x| match x.parse::<usize>().ok() {
-            None => None,
-            Some(x) => Some((x-start_time%x, x))
-        })
-        .collect::<Vec<(usize,usize)>>();
-    busline.sort_unstable();
-    let (delta, lineno) = busline[0];
-    Ok(delta*lineno)
+fn sol1(data: &str) -> Result<u64, ()> {
+    let schedule = data.parse::<Schedule>().map_err(|_| ())?;
+    schedule.next_departures(schedule.earliest, 1).map_err(|_| ())?.iter()
+        .map(|(id, times)| ((times[0] - schedule.earliest), *id))
+        .min()
+        .map(|(delta, id)| delta*id)
+        .ok_or(())
 }
 
+/// Bus `id` at index `idx` leaves at `t + idx`, so `t ≡ -idx (mod id)`
+/// for all of them, which the Chinese Remainder Theorem solves. IDs need
+/// not be pairwise coprime.
+///
 /// # Errors
 ///
-/// Returns () as error for lack of a better type
-fn sol2(data: &str) -> Result<usize, ()> {
-    let mut data = data.split('\n').skip(1).flat_map(|x|
-        x.split(',').enumerate()
-        .filter_map(|(idx, n)| match n.parse::<usize>() {
-            Err(_)  => None,
-            Ok(u)   => Some((idx, u))
-        }).collect::<Vec<(usize,usize)>>()
-    ).collect::<Vec<(usize,usize)>>();
-    data.sort_unstable();
-    let buslines: Vec<usize> = data.iter().map(|(_,x)| *x).collect();
-    let offsets: Vec<usize>  = data.iter().map(|(x,k)| (k*x-x)%k).collect();
-    let mut step = buslines[0];
-    let mut attempt = step;
-    for u in 1..buslines.len() {
-        loop {
-            // Check
-            if attempt%buslines[u] == offsets[u] {
-                // Ding!
-                step *= buslines[u];
-                break;
+/// Returns a [CrtError](CrtError) when the buses can never leave in turn,
+/// or when the answer is too big
+fn sol2(data: &str) -> Result<i128, CrtError> {
+    let congruences = parse_lines(data.split('\n').nth(1).unwrap_or("")).into_iter()
+        .map(|(idx, id)| Congruence::new(-(idx as i128), i128::from(id)))
+        .collect::<Result<Vec<Congruence>, CrtError>>()?;
+    Ok(crt(congruences)?.residue)
+}
+
+/// Most departures `query` lists for every line
+const MAX_DEPARTURES: usize = 1000;
+
+/// Most minutes `query` prints a timetable for
+const MAX_TIMETABLE: u64 = 1000;
+
+/// Answer a question about the schedule of the notes
+///
+///  - `next <t> <n>` gives the next `n` departures of every line from `t`,
+///    up to [MAX_DEPARTURES](MAX_DEPARTURES)
+///  - `apart <a> <b> <k> [t]` gives the first time from `t` (or the
+///    earliest time of the notes) when line `b` leaves `k` minutes after
+///    line `a`
+///  - `timetable <from> <to>` prints the departures between two times, at
+///    most [MAX_TIMETABLE](MAX_TIMETABLE) minutes
+///
+/// # Errors
+///
+/// Returns a message when the notes or the arguments make no sense
+fn query(data: &str, args: &[String]) -> Result<(), String> {
+    let schedule = data.parse::<Schedule>().map_err(|_| "bad notes")?;
+    let number = |i: usize| args.get(i).and_then(|n| n.parse::<i64>().ok())
+        .ok_or(format!("argument {} should be a number", i));
+    let time = |i: usize| args.get(i).and_then(|n| n.parse::<u64>().ok())
+        .ok_or(format!("argument {} should be a time", i));
+    match args.first().map(String::as_str) {
+        Some("next") => {
+            let n = usize::try_from(time(2)?).ok().filter(|&n| n <= MAX_DEPARTURES)
+                .ok_or(format!("at most {} departures at a time", MAX_DEPARTURES))?;
+            let next = schedule.next_departures(time(1)?, n).map_err(|e| format!("{:?}", e))?;
+            for (id, times) in next {
+                println!("bus {}: {}", id, times.iter().map(u64::to_string)
+                    .collect::<Vec<String>>().join(" "));
             }
-            attempt += step;
-        }
+        },
+        Some("apart") => {
+            let t = if args.len() > 4 { time(4)? } else { schedule.earliest };
+            let first = schedule.apart(time(1)?, time(2)?, number(3)?, t)
+                .map_err(|e| format!("{:?}", e))?;
+            println!("{}", first);
+        },
+        Some("timetable") => {
+            let (from, to) = (time(1)?, time(2)?);
+            if to.saturating_sub(from) >= MAX_TIMETABLE {
+                return Err(format!("at most {} minutes at a time", MAX_TIMETABLE));
+            }
+            println!("{}", schedule.timetable(from, to));
+        },
+        _ => return Err("expected next, apart or timetable".to_string())
     }
-    Ok(attempt)
+    Ok(())
 }
 
-
 fn main() {
     let tmp = read_data("input");
     if tmp.is_err() {
@@ -64,6 +99,14 @@ fn main() {
     }
     let data = tmp.unwrap();
 
+    let args = std::env::args().skip(1).collect::<Vec<String>>();
+    if !args.is_empty() {
+        if let Err(e) = query(&data, &args) {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
     println!("{:?}", sol1(&data));
     println!("{:?}", sol2(&data));
 }
@@ -78,9 +121,89 @@ mod test {
         assert_eq!(sol1(data), Ok(295));
     }
 
+    #[test]
+    fn schedule() {
+        let schedule = "939\n7,13,x,x,59,x,31,19".parse::<Schedule>().unwrap();
+        assert_eq!(schedule.lines, vec![(0, 7), (1, 13), (4, 59), (6, 31), (7, 19)]);
+        assert!("939\n7,0".parse::<Schedule>().is_err());
+        assert!("x\n7,13".parse::<Schedule>().is_err());
+        let next = schedule.next_departures(939, 3).unwrap();
+        assert_eq!(next[2], (59, vec![944, 1003, 1062]));
+        assert_eq!(next[0], (7, vec![945, 952, 959]));
+        // A departure right at t counts
+        assert_eq!(schedule.next_departures(949, 1).unwrap()[4], (19, vec![950]));
+        assert_eq!(schedule.next_departures(950, 1).unwrap()[4], (19, vec![950]));
+        // Departures past the end of time
+        assert_eq!(schedule.next_departures(u64::MAX - 1, 1), Err(QueryError::Overflow));
+        assert_eq!(schedule.next_departures(u64::MAX - 100, 10), Err(QueryError::Overflow));
+        assert_eq!(schedule.next_departures(u64::MAX - 100, 0).unwrap()[0], (7, vec![]));
+        let notes = "939\n7,13,x,x,59,x,31,19";
+        let args = |s: &str| s.split(' ').map(String::from).collect::<Vec<String>>();
+        assert!(query(notes, &args("next 939 3")).is_ok());
+        assert!(query(notes, &args("next 939 1001")).is_err());
+        assert!(query(notes, &args("next -1 3")).is_err());
+        // Times past what an i64 holds are still times
+        assert!(query(notes, &args("next 18446744073709551000 1")).is_ok());
+        // 7 leaves at 0 (mod 7) and 13 a minute later, from the example
+        assert_eq!(schedule.apart(7, 13, 1, 0), Ok(77));
+        assert_eq!(schedule.apart(7, 13, 1, 78), Ok(77 + 91));
+        assert_eq!(schedule.apart(13, 7, -1, 0), Ok(78));
+        assert_eq!(schedule.apart(7, 7, 0, 939), Ok(945));
+        assert_eq!(schedule.apart(7, 7, 3, 0), Err(QueryError::Crt(CrtError::Inconsistent)));
+        assert_eq!(schedule.apart(7, 11, 1, 0), Err(QueryError::UnknownLine(11)));
+    }
+
+    #[test]
+    fn timetable() {
+        let schedule = "939\n7,13,x,x,59,x,31,19".parse::<Schedule>().unwrap();
+        let table = schedule.timetable(943, 945);
+        assert_eq!(table, "time     bus 7   bus 13  bus 59  bus 31  bus 19\n\
+                           943        .       .       .       .       .\n\
+                           944        .       .       D       .       .\n\
+                           945        D       .       .       .       .");
+        let notes = "939\n7,13,x,x,59,x,31,19";
+        let args = |s: &str| s.split(' ').map(String::from).collect::<Vec<String>>();
+        assert!(query(notes, &args("timetable 0 999")).is_ok());
+        assert!(query(notes, &args("timetable 0 1000")).is_err());
+        assert!(query(notes, &args("timetable 0 18446744073709551615")).is_err());
+    }
+
     #[test]
     fn sol2_example() {
         let data = "939\n7,13,x,x,59,x,31,19";
         assert_eq!(sol2(data), Ok(1068781));
+        for &(lines, t) in &[("17,x,13,19", 3417), ("67,7,59,61", 754018),
+                             ("67,x,7,59,61", 779210), ("67,7,x,59,61", 1261476),
+                             ("1789,37,47,1889", 1202161486)] {
+            assert_eq!(sol2(&format!("0\n{}", lines)), Ok(t));
+        }
+    }
+
+    #[test]
+    fn crt_non_coprime() {
+        // t ≡ 0 (mod 6) and t ≡ 2 (mod 4)
+        assert_eq!(sol2("0\n6,x,4"), Ok(6));
+        assert_eq!(sol2("0\n4,x,6,x,x,x,10"), Ok(4));
+        // t is even, t + 1 too
+        assert_eq!(sol2("0\n2,2"), Err(CrtError::Inconsistent));
+        assert_eq!(sol2("0\n7,0"), Err(CrtError::BadModulus));
+        let system = [Congruence::new(3, 12).unwrap(), Congruence::new(7, 8).unwrap(),
+                      Congruence::new(-3, 18).unwrap()];
+        assert_eq!(crt(system.iter().copied()), Ok(Congruence { residue: 15, modulus: 72 }));
+        assert_eq!(crt(std::iter::empty()), Ok(Congruence::ANY));
+    }
+
+    #[test]
+    fn crt_large() {
+        // Primes around 10^18, whose products overflow an u64 and then an i128
+        let primes = [1_000_000_000_000_000_003_i128, 1_000_000_000_000_000_009,
+                      999_999_999_999_999_989];
+        let system = primes.iter().enumerate()
+            .map(|(i, &p)| Congruence::new(-(i as i128), p).unwrap()).collect::<Vec<_>>();
+        let t = crt(system[..2].iter().copied()).unwrap();
+        assert_eq!(t.modulus, primes[0] * primes[1]);
+        assert!(system[..2].iter().all(|c| t.residue.rem_euclid(c.modulus) == c.residue));
+        assert_eq!(crt(system), Err(CrtError::Overflow));
+        assert_eq!(mul_mod(primes[0] - 1, primes[0] - 2, primes[0]), 2);
     }
 }
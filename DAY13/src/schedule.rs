@@ -0,0 +1,110 @@
+/// Parse a list of bus lines such as `7,13,x,x,59`, returning the lines in
+/// service as `(index in the list, ID)`, and skipping the `x`
+fn parse_lines(list: &str) -> Vec<(usize, u64)> {
+    list.split(',').enumerate()
+        .filter_map(|(idx, n)| n.trim().parse::<u64>().ok().map(|id| (idx, id)))
+        .collect()
+}
+
+/// The notes of the day: the earliest time we can leave, and the bus lines
+/// in service, the bus of line `id` leaving every `id` minutes from time 0
+#[derive(Debug,Clone,PartialEq,Eq)]
+struct Schedule {
+    earliest: u64,
+    /// Lines as `(index in the list, ID)`
+    lines: Vec<(usize, u64)>
+}
+
+/// Error thrown when the notes cannot be parsed
+#[derive(Debug)]
+struct ScheduleParseError;
+
+impl std::str::FromStr for Schedule {
+    type Err = ScheduleParseError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut datasplit = s.trim().split('\n');
+        let earliest = datasplit.next().and_then(|t| t.trim().parse::<u64>().ok())
+            .ok_or(ScheduleParseError)?;
+        let lines = parse_lines(datasplit.next().ok_or(ScheduleParseError)?);
+        if lines.iter().any(|&(_, id)| id == 0) {
+            return Err(ScheduleParseError);
+        }
+        Ok(Schedule { earliest, lines })
+    }
+}
+
+/// Reasons why a question about the schedule has no answer
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+enum QueryError {
+    /// No bus of that line is in service
+    UnknownLine(u64),
+    /// The departures asked for never happen, or too late to tell
+    Crt(CrtError),
+    /// A departure asked for is too late to be told
+    Overflow
+}
+
+impl Schedule {
+    /// The first departure of line `id` at or after time `t`, if it is
+    /// not too late to be told
+    fn next_departure(id: u64, t: u64) -> Option<u64> {
+        t.div_ceil(id).checked_mul(id)
+    }
+
+    /// The next `n` departures of every line at or after time `t`, in the
+    /// order of the list
+    ///
+    /// # Errors
+    ///
+    /// Returns `QueryError::Overflow` when a departure does not fit in an
+    /// `u64`
+    fn next_departures(&self, t: u64, n: usize) -> Result<Vec<(u64, Vec<u64>)>, QueryError> {
+        self.lines.iter().map(|&(_, id)| {
+            let first = Schedule::next_departure(id, t).ok_or(QueryError::Overflow)?;
+            let times = (0..n as u64)
+                .map(|i| i.checked_mul(id).and_then(|wait| first.checked_add(wait)))
+                .collect::<Option<Vec<u64>>>()
+                .ok_or(QueryError::Overflow)?;
+            Ok((id, times))
+        }).collect()
+    }
+
+    /// The first time at or after `t` when line `a` leaves, and line `b`
+    /// leaves `k` minutes later (or earlier, if `k` is negative)
+    ///
+    /// # Errors
+    ///
+    /// Returns a [QueryError](QueryError) when a line is not in service, or
+    /// when the two never leave `k` minutes apart
+    fn apart(&self, a: u64, b: u64, k: i64, t: u64) -> Result<u64, QueryError> {
+        for &id in &[a, b] {
+            if self.lines.iter().all(|&(_, line)| line != id) {
+                return Err(QueryError::UnknownLine(id));
+            }
+        }
+        let both = crt(vec![
+            Congruence::new(0, i128::from(a)).map_err(QueryError::Crt)?,
+            Congruence::new(-i128::from(k), i128::from(b)).map_err(QueryError::Crt)?
+        ]).map_err(QueryError::Crt)?;
+        // The first time of the congruence at or after t
+        let t = i128::from(t);
+        let first = t + (both.residue - t).rem_euclid(both.modulus);
+        u64::try_from(first).map_err(|_| QueryError::Crt(CrtError::Overflow))
+    }
+
+    /// The departures of every line from time `from` to time `to`, one
+    /// minute per row, laid out as in the puzzle
+    fn timetable(&self, from: u64, to: u64) -> String {
+        let header = self.lines.iter().map(|&(_, id)| format!("{:<8}", format!("bus {}", id)))
+            .collect::<String>();
+        let mut res = format!("{:<9}{}", "time", header).trim_end().to_string();
+        for t in from..=to {
+            let row = self.lines.iter()
+                .map(|&(_, id)| if t % id == 0 { "  D     " } else { "  .     " })
+                .collect::<String>();
+            res += "\n";
+            res += format!("{:<9}{}", t, row).trim_end();
+        }
+        res
+    }
+}
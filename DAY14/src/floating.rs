@@ -0,0 +1,71 @@
+/// Memory of the version 2 decoder, where writes go to whole sets of
+/// addresses at once
+///
+/// Every set of addresses is a [BitMask](BitMask) whose `mask` holds the
+/// floating bits and whose `application` holds the value of the others.
+/// Regions are kept disjoint: a new write is carved out of every region it
+/// overlaps before it is added, so that addresses are never enumerated,
+/// however many floating bits there are.
+struct FloatingMemory {
+    regions: Vec<(BitMask, usize)>
+}
+
+impl BitMask {
+    /// The set of addresses `addr` is decoded to by a version 2 mask
+    fn decode(&self, addr: usize) -> BitMask {
+        BitMask {
+            application: (addr | self.application) & !self.mask,
+            mask: self.mask
+        }
+    }
+
+    /// Number of addresses in a set
+    fn count(&self) -> u128 {
+        1 << self.mask.count_ones()
+    }
+
+    /// Whether two sets of addresses share any, which they do unless a
+    /// bit fixed in both has different values
+    fn intersects(&self, other: &BitMask) -> bool {
+        (self.application ^ other.application) & !self.mask & !other.mask == 0
+    }
+
+    /// The addresses of `self` that are not in `other`, as disjoint sets
+    ///
+    /// Every bit floating in `self` but fixed in `other` splits off the
+    /// addresses where it differs from `other`, and the rest goes on with
+    /// the bit fixed to the value in `other`.
+    fn subtract(&self, other: &BitMask) -> Vec<BitMask> {
+        if !self.intersects(other) {
+            return vec![*self];
+        }
+        let mut res = Vec::new();
+        let mut rest = *self;
+        let splits = self.mask & !other.mask;
+        for bit in (0..usize::BITS).map(|b| 1 << b).filter(|b| splits & b != 0) {
+            rest.mask &= !bit;
+            res.push(BitMask { mask: rest.mask, application: rest.application | (!other.application & bit) });
+            rest.application |= other.application & bit;
+        }
+        res
+    }
+}
+
+impl FloatingMemory {
+    fn new() -> Self {
+        FloatingMemory { regions: Vec::new() }
+    }
+
+    /// Write `value` to every address of `addresses`
+    fn write(&mut self, addresses: BitMask, value: usize) {
+        self.regions = self.regions.iter()
+            .flat_map(|&(region, v)| region.subtract(&addresses).into_iter().map(move |r| (r, v)))
+            .collect();
+        self.regions.push((addresses, value));
+    }
+
+    /// Sum of the values at every address
+    fn sum(&self) -> u128 {
+        self.regions.iter().map(|(region, v)| region.count() * *v as u128).sum()
+    }
+}
@@ -3,10 +3,12 @@ extern crate regex;
 
 use std::fs::File;
 use std::io::prelude::*;
-use std::collections::{HashMap, HashSet};
+use std::collections::HashMap;
 
 use regex::Regex;
 
+include!("floating.rs");
+
 lazy_static! {
     static ref MASKLINE: Regex = Regex::new(r"^mask\s+=\s+([X01]+)$").unwrap();
     static ref MEMOLINE: Regex = Regex::new(r"^mem\[(\d+)\]\s+=\s+(\d+)$").unwrap();
@@ -44,54 +46,6 @@ impl BitMask {
     fn operate(&self, val: usize) -> usize {
         (self.mask & val) | self.application
     }
-
-    fn get_mask(&self) -> usize { self.mask }
-    fn get_application(&self) -> usize { self.application }
-    //fn get_base(&self) -> usize { self.application & (!self.mask) }
-    fn string(&self) -> String {
-        (0..=37).map(|idx| {
-            match (self.mask & (1<<idx), self.application & (1<<idx)) {
-                (0,0) => '0',
-                (0,_) => '1',
-                (_,_) => 'X'
-            }
-        }).rev().collect::<String>()
-    }
-    fn generate(&self) -> HashSet<usize> {
-        let mut possibilities: HashSet<usize> = HashSet::new();
-
-        let mut s = self.string();
-        let chidx = s.chars()
-            .enumerate()
-            .filter_map(|(e,x)| match x {
-                'X' => Some(e),
-                _ => None
-            }).collect::<Vec<usize>>();
-        s = s.replace('X', "0");
-        possibilities.insert(usize::from_str_radix(&s, 2).unwrap());
-        for _ in 0..usize::pow(2, self.get_mask().count_ones()) {
-            for i in (0_usize..=37_usize).rev() {
-                if chidx.contains(&i) {
-                    match s.chars().nth(i).unwrap() {
-                        '0' => {
-                            // Flip it to one
-                            s.replace_range(i..=i, "1");
-                            possibilities.insert(
-                                usize::from_str_radix(&s, 2).unwrap());
-                            break; // Start from scratch again
-                        },
-                        '1' => {
-                            // Flip to zero and go to next
-                            s.replace_range(i..=i, "0");
-                        }
-                        _ => panic!("Weep")
-                    }
-                }
-            }
-        }
-
-        possibilities
-    }
 }
 
 /// # Errors
@@ -116,16 +70,19 @@ fn sol1(data: &str) -> Result<usize,()> {
             return Err(());
         }
     }
-    Ok(mem.iter().map(|(_,x)| *x).sum::<usize>())
+    Ok(mem.values().sum::<usize>())
 }
 
+/// Addresses are decoded into sets that are never expanded, so floating
+/// bits cost nothing, and the sum may not fit in an `usize`.
+///
 /// # Errors
 ///
 /// Returns ()
-fn sol2(data: &str) -> Result<usize, ()> {
+fn sol2(data: &str) -> Result<u128, ()> {
     let datalines = data.split('\n').collect::<Vec<&str>>();
     let mut bitmask: BitMask = BitMask{mask: 0, application: 0};
-    let mut mem: HashMap<usize,usize> = HashMap::new();
+    let mut mem = FloatingMemory::new();
     for line in datalines {
         // Attempt to parse mask line
         if let Some(mdata) = MASKLINE.captures(line) {
@@ -135,20 +92,13 @@ fn sol2(data: &str) -> Result<usize, ()> {
             let (maddr, mval) =
                 (mdata[1].parse::<usize>().unwrap(),
                     mdata[2].parse::<usize>().unwrap());
-            let general_address_mask = BitMask {
-                application: (maddr | bitmask.get_application())
-                    & (!bitmask.get_mask()),
-                mask: bitmask.get_mask()
-            };
-            for addr in general_address_mask.generate() {
-                mem.insert(addr, mval);
-            }
+            mem.write(bitmask.decode(maddr), mval);
         } else {
             println!("PANIC: \"{}\"", line);
             return Err(());
         }
     }
-    Ok(mem.iter().map(|(_,x)| *x).sum::<usize>())
+    Ok(mem.sum())
 }
 
 fn main() {
@@ -176,4 +126,40 @@ mod test {
         let data = "mask = 000000000000000000000000000000X1001X\nmem[42] = 100\nmask = 00000000000000000000000000000000X0XX\nmem[26] = 1";
         assert_eq!(sol2(data), Ok(208));
     }
+
+    /// Every address of a set, one floating bit after the other
+    fn expand(set: &BitMask) -> Vec<usize> {
+        (0..usize::BITS).map(|b| 1 << b).filter(|b| set.mask & b != 0)
+            .fold(vec![set.application], |addrs, bit|
+                addrs.iter().flat_map(|a| vec![*a, a | bit]).collect())
+    }
+
+    #[test]
+    fn subtract() {
+        let sets = ["X1X0", "XXXX", "0X1X", "1100", "XX00", "0000", "X0X1"].iter()
+            .map(|s| s.parse::<BitMask>().unwrap()).collect::<Vec<BitMask>>();
+        for a in &sets {
+            for b in &sets {
+                let mut left = a.subtract(b).iter().flat_map(expand).collect::<Vec<usize>>();
+                let mut expected = expand(a).into_iter().filter(|x| !expand(b).contains(x))
+                    .collect::<Vec<usize>>();
+                // Pieces are disjoint, so sorting shows no duplicates
+                left.sort_unstable();
+                expected.sort_unstable();
+                assert_eq!(left, expected);
+                assert_eq!(a.intersects(b), expand(a).iter().any(|x| expand(b).contains(x)));
+            }
+        }
+    }
+
+    #[test]
+    fn many_floating_bits() {
+        let all = "X".repeat(36);
+        let data = format!("mask = {}\nmem[0] = 1\nmask = 1{}\nmem[0] = 3\nmask = 11{}\nmem[5] = 2",
+                           all, &all[1..], &all[2..]);
+        // Half the memory holds 1, a quarter 3 and a quarter 2
+        assert_eq!(sol2(&data), Ok((1 << 35) + 3 * (1 << 34) + 2 * (1 << 34)));
+        let data = format!("mask = {}\nmem[0] = {}", all, usize::MAX >> 28);
+        assert_eq!(sol2(&data), Ok((1 << 36) * u128::from(u64::MAX >> 28)));
+    }
 }
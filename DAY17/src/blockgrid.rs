@@ -0,0 +1,284 @@
+/// Number of cells along every axis but `x` in a block
+const BLOCK_SIDE: usize = 4;
+/// Number of cells along `x` in a block (i.e. bits in a row word)
+const ROW_BITS: isize = 64;
+/// Number of bit planes in a counter. Counts reach `3^dims`, so this bounds
+/// the number of dimensions to [`MAX_DIMS`].
+const PLANES: usize = 16;
+/// Largest number of dimensions whose counts fit in [`PLANES`] planes
+const MAX_DIMS: usize = 10;
+
+/// Block coordinates along every axis
+type BlockKey = Vec<isize>;
+/// A bit-sliced counter for 64 cells
+type Counter = [u64; PLANES];
+
+#[derive(Clone)]
+struct Block {
+    rows: Vec<u64>
+}
+
+impl Block {
+    fn new(dims: usize) -> Self {
+        Block { rows: vec![0; BLOCK_SIDE.pow((dims - 1) as u32)] }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.rows.iter().all(|&r| r == 0)
+    }
+}
+
+/// # Sparse block storage
+///
+/// The board is cut into blocks. Along `x`, a block covers 64 cells packed
+/// into the bits of a single `u64` (bit `i` is `x = 64*kx + i`). Along every
+/// other axis, a block covers [`BLOCK_SIDE`] cells, so a block of a
+/// `dims`-dimensional board holds `BLOCK_SIDE^(dims-1)` such rows. Only
+/// blocks that contain at least one active cell are kept in the map.
+///
+/// Axes are numbered from the packed one : `0` is `x`, `1` is `y`, and every
+/// axis from `2` onwards (`z`, `w`, ...) is *mirrored*. The seed only ever
+/// lives in the `z=0, w=0, ...` slice, and the rules are isotropic, so the
+/// board stays symmetric around `0` on those axes forever. Only the
+/// non-negative half is stored; reads of negative coordinates are reflected.
+///
+/// ## Neighbour counting
+///
+/// The `3^dims` box around each cell is separable, so it is summed one axis
+/// at a time on bit-sliced counters (plane `k` of a counter holds bit `k` of
+/// the count of 64 cells at once) :
+///  - along `x`, with shifts of the row words and their neighbours' edge bits,
+///  - then along every other axis, by adding three consecutive counters.
+///
+/// Each block only looks its `3^dims` neighbouring blocks up once per step.
+#[derive(Clone)]
+struct BlockGrid {
+    dims: usize,
+    blocks: HashMap<BlockKey, Block>
+}
+
+/// Add two bit-sliced counters, `width` planes deep
+fn add_counters(a: &Counter, b: &Counter, width: usize) -> Counter {
+    let mut res = [0; PLANES];
+    let mut carry = 0;
+    for k in 0..width {
+        res[k] = a[k] ^ b[k] ^ carry;
+        carry = (a[k] & b[k]) | (carry & (a[k] ^ b[k]));
+    }
+    res
+}
+
+/// Mask of the cells of `c` whose count is exactly `t`
+fn counter_equals(c: &Counter, t: usize, width: usize) -> u64 {
+    if t >> width != 0 {
+        return 0;
+    }
+    (0..width).fold(!0, |mask, k|
+        mask & if (t >> k) & 1 == 1 { c[k] } else { !c[k] })
+}
+
+/// Index of a multi-index in a row-major array (first axis fastest)
+fn flat_index(idx: &[usize], shape: &[usize]) -> usize {
+    idx.iter().zip(shape).rev().fold(0, |acc, (&i, &s)| acc * s + i)
+}
+
+/// Inverse of [`flat_index`]
+fn multi_index(mut flat: usize, shape: &[usize]) -> Vec<usize> {
+    shape.iter().map(|&s| { let i = flat % s; flat /= s; i }).collect()
+}
+
+impl BlockGrid {
+    pub fn new(dims: usize) -> Self {
+        assert!((2..=MAX_DIMS).contains(&dims), "Unsupported dimension count");
+        BlockGrid { dims, blocks: HashMap::new() }
+    }
+
+    /// Canonical (stored) version of a position, folding mirrored axes
+    fn canonical(&self, p: &[isize]) -> Vec<isize> {
+        p.iter().enumerate()
+            .map(|(i, &c)| if i >= 2 { c.abs() } else { c })
+            .collect()
+    }
+
+    /// Split a canonical position into block key, row index and bit
+    fn locate(&self, p: &[isize]) -> (BlockKey, usize, u32) {
+        let side = BLOCK_SIDE as isize;
+        let mut key = vec![p[0].div_euclid(ROW_BITS)];
+        key.extend(p[1..].iter().map(|c| c.div_euclid(side)));
+        let locals = p[1..].iter()
+            .map(|c| c.rem_euclid(side) as usize)
+            .collect::<Vec<usize>>();
+        let shape = vec![BLOCK_SIDE; self.dims - 1];
+        (key, flat_index(&locals, &shape), p[0].rem_euclid(ROW_BITS) as u32)
+    }
+
+    pub fn get(&self, p: &[isize]) -> bool {
+        let (key, row, bit) = self.locate(&self.canonical(p));
+        self.blocks.get(&key)
+            .is_some_and(|b| (b.rows[row] >> bit) & 1 == 1)
+    }
+
+    pub fn set(&mut self, p: &[isize], state: bool) {
+        let (key, row, bit) = self.locate(&self.canonical(p));
+        let dims = self.dims;
+        let block = self.blocks.entry(key.clone())
+            .or_insert_with(|| Block::new(dims));
+        if state {
+            block.rows[row] |= 1 << bit;
+        } else {
+            block.rows[row] &= !(1 << bit);
+            if block.is_empty() {
+                self.blocks.remove(&key);
+            }
+        }
+    }
+
+    /// All the stored active cells, in sorted order
+    pub fn active_cells(&self) -> Vec<Vec<isize>> {
+        let shape = vec![BLOCK_SIDE; self.dims - 1];
+        let mut cells = Vec::new();
+        for (key, block) in &self.blocks {
+            for (r, &row) in block.rows.iter().enumerate() {
+                let locals = multi_index(r, &shape);
+                for bit in (0..ROW_BITS).filter(|b| (row >> b) & 1 == 1) {
+                    let mut cell = vec![key[0] * ROW_BITS + bit];
+                    cell.extend(locals.iter().zip(&key[1..])
+                        .map(|(&l, &k)| k * BLOCK_SIDE as isize + l as isize));
+                    cells.push(cell);
+                }
+            }
+        }
+        cells.sort_unstable();
+        cells
+    }
+
+    /// The same board, embedded in `dims` dimensions
+    pub fn lift(&self, dims: usize) -> Self {
+        let mut grid = BlockGrid::new(dims);
+        for mut cell in self.active_cells() {
+            cell.resize(dims, 0);
+            grid.set(&cell, true);
+        }
+        grid
+    }
+
+    /// Number of active cells, counting the mirrored halves
+    pub fn population(&self) -> usize {
+        let shape = vec![BLOCK_SIDE; self.dims - 1];
+        self.blocks.iter().map(|(key, block)| {
+            block.rows.iter().enumerate().map(|(r, row)| {
+                let mirrors = multi_index(r, &shape).iter().zip(&key[1..])
+                    .skip(1)
+                    .filter(|(&l, &k)| k * BLOCK_SIDE as isize + l as isize != 0)
+                    .count();
+                (row.count_ones() as usize) << mirrors
+            }).sum::<usize>()
+        }).sum()
+    }
+
+    /// Advance one generation.
+    ///
+    /// `survive[t]` (resp. `born[t]`) tells whether an active (resp.
+    /// inactive) cell is active afterwards when the `3^dims` box around it,
+    /// itself included, holds `t` active cells.
+    pub fn step(&mut self, survive: &[bool], born: &[bool]) {
+        let mut candidates: HashSet<BlockKey> = HashSet::new();
+        let offsets = 3_usize.pow(self.dims as u32);
+        for key in self.blocks.keys() {
+            for o in 0..offsets {
+                let cand = multi_index(o, &vec![3; self.dims]).iter()
+                    .zip(key)
+                    .map(|(&d, &k)| k + d as isize - 1)
+                    .collect::<BlockKey>();
+                if cand[2..].iter().all(|&k| k >= 0) {
+                    candidates.insert(cand);
+                }
+            }
+        }
+        self.blocks = candidates.into_iter()
+            .map(|key| { let b = self.next_block(&key, survive, born); (key, b) })
+            .filter(|(_, b)| !b.is_empty())
+            .collect();
+    }
+
+    fn next_block(&self, key: &[isize], survive: &[bool], born: &[bool]) -> Block {
+        let dims = self.dims;
+        let side = BLOCK_SIDE as isize;
+        // Look every neighbouring block up once
+        let hood_shape = vec![3; dims];
+        let hood = (0..3_usize.pow(dims as u32)).map(|o| {
+            let nkey = multi_index(o, &hood_shape).iter().zip(key)
+                .map(|(&d, &k)| k + d as isize - 1)
+                .collect::<BlockKey>();
+            self.blocks.get(&nkey)
+        }).collect::<Vec<Option<&Block>>>();
+        let block_shape = vec![BLOCK_SIDE; dims - 1];
+        // Fetch a row word at block offset `dx` along x and padded local
+        // position `p` (from -1 to BLOCK_SIDE) along the other axes
+        let fetch = |dx: usize, p: &[usize]| -> u64 {
+            let mut hidx = vec![dx];
+            let mut locals = Vec::with_capacity(dims - 1);
+            for (i, &pi) in p.iter().enumerate() {
+                let mut c = key[i + 1] * side + pi as isize - 1;
+                if i >= 1 && c < 0 {
+                    c = -c;
+                }
+                hidx.push((c.div_euclid(side) - key[i + 1] + 1) as usize);
+                locals.push(c.rem_euclid(side) as usize);
+            }
+            hood[flat_index(&hidx, &hood_shape)]
+                .map_or(0, |b| b.rows[flat_index(&locals, &block_shape)])
+        };
+        // Sum along x
+        let mut shape = vec![BLOCK_SIDE + 2; dims - 1];
+        let mut counters = (0..shape.iter().product()).map(|f| {
+            let p = multi_index(f, &shape);
+            let (l, m, r) = (fetch(0, &p), fetch(1, &p), fetch(2, &p));
+            let a = (m << 1) | (l >> (ROW_BITS - 1));
+            let c = (m >> 1) | (r << (ROW_BITS - 1));
+            let mut counter = [0; PLANES];
+            counter[0] = a ^ m ^ c;
+            counter[1] = (a & m) | (c & (a ^ m));
+            counter
+        }).collect::<Vec<Counter>>();
+        let mut width = 2;
+        // Then along every other axis
+        for axis in 0..dims - 1 {
+            width = (width + 2).min(PLANES);
+            let mut next_shape = shape.clone();
+            next_shape[axis] -= 2;
+            counters = (0..next_shape.iter().product()).map(|f| {
+                let mut p = multi_index(f, &next_shape);
+                let mut sum = counters[flat_index(&p, &shape)];
+                for _ in 0..2 {
+                    p[axis] += 1;
+                    sum = add_counters(&sum, &counters[flat_index(&p, &shape)], width);
+                }
+                sum
+            }).collect();
+            shape = next_shape;
+        }
+        // Apply the rule
+        let own = hood[(hood.len() - 1) / 2];
+        let survive_totals = (0..survive.len()).filter(|&t| survive[t]).collect::<Vec<usize>>();
+        let born_totals = (0..born.len()).filter(|&t| born[t]).collect::<Vec<usize>>();
+        let rows = counters.iter().enumerate().map(|(r, counter)| {
+            let old = own.map_or(0, |b| b.rows[r]);
+            let stays = survive_totals.iter()
+                .fold(0, |m, &t| m | counter_equals(counter, t, width));
+            let appears = born_totals.iter()
+                .fold(0, |m, &t| m | counter_equals(counter, t, width));
+            (old & stays) | (!old & appears)
+        }).collect();
+        Block { rows }
+    }
+
+    /// Smallest and largest stored coordinates along every axis
+    pub fn bounds(&self) -> Option<Vec<(isize, isize)>> {
+        let cells = self.active_cells();
+        let first = cells.first()?;
+        Some((0..self.dims).map(|i| cells.iter()
+            .fold((first[i], first[i]), |(lo, hi), c| (lo.min(c[i]), hi.max(c[i]))))
+            .collect())
+    }
+}
@@ -0,0 +1,133 @@
+// # Slice export
+//
+// Printing every slice one after the other quickly stops being readable,
+// so a board can also be drawn as a contact sheet : a grid of `y×x`
+// tiles, one per slice, with `z` growing along the columns and `w` (then
+// every further axis) along the rows. Mirrored slices are drawn too, so
+// the sheet is symmetric around its central row and column.
+//
+// The sheet is written as a plain PBM (`P1`) image, readable by about any
+// image viewer or converter. Tiles are framed by one-pixel black lines and
+// get an empty one-cell margin so active cells never touch the frame.
+//
+// Alongside, the active coordinates of every generation can be dumped as
+// JSON, to be plotted with whatever tool is at hand.
+
+/// Every active cell of the board, mirrored halves included
+fn all_active_cells(board: &GollyBoard) -> Vec<Vec<isize>> {
+    let mut cells = board.grid.active_cells().into_iter().flat_map(|cell| {
+        let mut images = vec![cell.clone()];
+        for axis in (2..cell.len()).filter(|&a| cell[a] != 0) {
+            let reflected = images.iter().map(|img| {
+                let mut img = img.clone();
+                img[axis] = -img[axis];
+                img
+            }).collect::<Vec<Vec<isize>>>();
+            images.extend(reflected);
+        }
+        images
+    }).collect::<Vec<Vec<isize>>>();
+    cells.sort_unstable();
+    cells
+}
+
+/// Render the board as a PBM contact sheet, drawing each cell as a
+/// `scale×scale` square
+fn contact_sheet(board: &GollyBoard, scale: usize) -> String {
+    let bounds = board.grid.bounds()
+        .unwrap_or_else(|| vec![(0, 0); board.grid.dims]);
+    // Mirrored axes span both sides of 0
+    let ranges = bounds.iter().enumerate()
+        .map(|(i, &(lo, hi))| if i >= 2 { (-hi, hi) } else { (lo - 1, hi + 1) })
+        .collect::<Vec<(isize, isize)>>();
+    let span = |(lo, hi): (isize, isize)| (hi - lo + 1) as usize;
+    let columns = ranges.get(2).map_or(1, |&r| span(r));
+    let rows = ranges.iter().skip(3).map(|&r| span(r)).product::<usize>();
+    let (tile_w, tile_h) = (span(ranges[0]) * scale, span(ranges[1]) * scale);
+    let width = columns * (tile_w + 1) + 1;
+    let height = rows * (tile_h + 1) + 1;
+
+    let mut pixels = vec![vec![true; width]; height];
+    let mut p = vec![0; ranges.len()];
+    for row in 0..rows {
+        // Decode the row into w, then the further axes
+        let mut rest = row;
+        for (axis, &r) in ranges.iter().enumerate().skip(3) {
+            p[axis] = r.0 + (rest % span(r)) as isize;
+            rest /= span(r);
+        }
+        for col in 0..columns {
+            if let Some(&(lo, _)) = ranges.get(2) {
+                p[2] = lo + col as isize;
+            }
+            let (top, left) = (row * (tile_h + 1) + 1, col * (tile_w + 1) + 1);
+            for py in 0..tile_h {
+                p[1] = ranges[1].0 + (py / scale) as isize;
+                for px in 0..tile_w {
+                    p[0] = ranges[0].0 + (px / scale) as isize;
+                    pixels[top + py][left + px] = board.get_at(&p);
+                }
+            }
+        }
+    }
+
+    let mut res = format!("P1\n# {}\n{} {}\n", sheet_legend(&ranges), width, height);
+    for line in pixels {
+        // PBM lines should stay under 70 characters
+        for chunk in line.chunks(64) {
+            res.extend(chunk.iter().map(|&b| if b { '1' } else { '0' }));
+            res.push('\n');
+        }
+    }
+    res
+}
+
+/// Describe which slice lives where on the sheet
+fn sheet_legend(ranges: &[(isize, isize)]) -> String {
+    let mut legend = format!("tiles x={}..{} y={}..{}",
+        ranges[0].0, ranges[0].1, ranges[1].0, ranges[1].1);
+    if let Some(&(lo, hi)) = ranges.get(2) {
+        legend.push_str(&format!("; columns z={}..{}", lo, hi));
+    }
+    for (axis, &(lo, hi)) in ranges.iter().enumerate().skip(3) {
+        legend.push_str(&format!("; rows {}={}..{}", axis_name(axis), lo, hi));
+    }
+    legend
+}
+
+/// JSON object listing the active cells of a board at a given generation
+fn generation_json(board: &GollyBoard, generation: usize) -> String {
+    let cells = all_active_cells(board).iter()
+        .map(|c| format!("[{}]", c.iter()
+            .map(|v| v.to_string()).collect::<Vec<String>>().join(",")))
+        .collect::<Vec<String>>();
+    let axes = (0..board.grid.dims)
+        .map(|a| format!("\"{}\"", axis_name(a)))
+        .collect::<Vec<String>>();
+    format!("{{\"generation\":{},\"axes\":[{}],\"active\":[{}]}}",
+        generation, axes.join(","), cells.join(","))
+}
+
+/// Run `cycles` steps of the board, writing a contact sheet per generation
+/// (`gen_N.pbm`) and the JSON history of active cells (`history.json`)
+/// into `dir`.
+///
+/// # Errors
+///
+/// Returns any I/O error met while writing the files.
+fn export_run(mut board: GollyBoard, cycles: usize, scale: usize, dir: &str)
+    -> std::io::Result<()> {
+    std::fs::create_dir_all(dir)?;
+    let path = std::path::Path::new(dir);
+    let mut history = Vec::new();
+    for generation in 0..=cycles {
+        if generation > 0 {
+            board.step();
+        }
+        File::create(path.join(format!("gen_{}.pbm", generation)))?
+            .write_all(contact_sheet(&board, scale).as_bytes())?;
+        history.push(generation_json(&board, generation));
+    }
+    File::create(path.join("history.json"))?
+        .write_all(format!("[\n{}\n]\n", history.join(",\n")).as_bytes())
+}
@@ -9,172 +9,134 @@ fn read_data(filepath: &str) -> std::io::Result<String> {
     Ok(contents.trim().to_string())
 }
 
-type Position = (isize, isize, isize, isize);
-type DataGrid = HashMap<Position,bool>;
+include!("blockgrid.rs");
+include!("export.rs");
+
+type Position = [isize];
 type ChoiceFunction = fn(usize, usize, bool) -> bool;
-type VisibilityFunction = fn(Position) -> Vec<Position>;
+
+/// The usual rules : survive with 2 or 3 neighbours, be born with 3
+const CONWAY: ChoiceFunction = |_, busy, oldstate| {
+    if oldstate {
+        busy == 2 || busy == 3
+    } else {
+        busy == 3
+    }
+};
+
+/// Name of an axis when printing slices
+fn axis_name(axis: usize) -> String {
+    match axis {
+        0 => "x".to_string(),
+        1 => "y".to_string(),
+        2 => "z".to_string(),
+        3 => "w".to_string(),
+        _ => format!("d{}", axis)
+    }
+}
 
 #[derive(Clone)]
 struct GollyBoard {
-    map: DataGrid,
-    visibility: HashMap<Position,Vec<Position>>,
-    changefunction: Option<ChoiceFunction>,
-    visibilityfunction: Option<VisibilityFunction>
+    grid: BlockGrid,
+    changefunction: Option<ChoiceFunction>
 }
 
 impl std::str::FromStr for GollyBoard {
     type Err = std::str::Utf8Error;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        //let linecount = s.chars().filter(|&x| x=='\n').count()+1;
-        let linewidth = s.chars().position(|x| x=='\n').unwrap();
-        let map = s.chars().filter(|&x| x!='\n').enumerate()
-            .filter_map(|(pos,c)|
+        let mut grid = BlockGrid::new(2);
+        for (y, line) in s.lines().enumerate() {
+            for (x, c) in line.chars().enumerate() {
                 match c {
-                    '.' => None,
-                    '#' =>
-                        Some((
-                            (0_isize, 0_isize,
-                             (pos/linewidth) as isize,
-                             (pos%linewidth) as isize),
-                             true)),
+                    '.' => (),
+                    '#' => grid.set(&[x as isize, y as isize], true),
                     _ => panic!("Weep")
-        })
-        .collect::<DataGrid>();
-        //println!("Initialized with height={} and width={}", linecount, linewidth);
+                }
+            }
+        }
         Ok(GollyBoard {
-            map,
-            visibility: HashMap::new(),
-            changefunction: None,
-            visibilityfunction: None
+            grid,
+            changefunction: None
         })
     }
 }
 
 impl std::fmt::Display for GollyBoard {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let mut stres: String = String::new();
-        for w in self.get_minw()..=self.get_maxw() {
-            for z in self.get_minz()..=self.get_maxz() {
-                if z > 0 { stres.push('\n'); }
-                stres.push_str(format!("\nz={}, w={}", z, w).as_str());
-                for y in self.get_miny()..=self.get_maxy() {
-                    stres.push('\n');
-                    for x in self.get_minx()..=self.get_maxx() {
-                        stres.push(
-                            if self.get_at((w,z, y, x)) {
-                                '#'
-                            } else {
-                                '.'
-                            }
-                        )
-                    }
+        let bounds = match self.grid.bounds() {
+            Some(b) => b,
+            None => return Ok(())
+        };
+        // Slices are headed by z and w even on fewer dimensions, w being
+        // the outer loop, then any further axis. Those axes are mirrored,
+        // so they span both sides of 0
+        let mut ranges = bounds[2..].iter().map(|&(_, hi)| (-hi, hi)).collect::<Vec<_>>();
+        ranges.resize(ranges.len().max(2), (0, 0));
+        let mut slice = ranges.iter().map(|&(lo, _)| lo).collect::<Vec<isize>>();
+        let mut p = vec![0; bounds.len()];
+        loop {
+            if slice[0] > 0 { writeln!(f)?; }
+            let header = slice.iter().enumerate()
+                .map(|(i, c)| format!("{}={}", axis_name(i + 2), c))
+                .collect::<Vec<String>>();
+            write!(f, "\n{}", header.join(", "))?;
+            let extra = p.len() - 2;
+            p[2..].copy_from_slice(&slice[..extra]);
+            for y in bounds[1].0..=bounds[1].1 {
+                p[1] = y;
+                writeln!(f)?;
+                for x in bounds[0].0..=bounds[0].1 {
+                    p[0] = x;
+                    write!(f, "{}", if self.get_at(&p) { '#' } else { '.' })?;
                 }
             }
+            // Next slice, z varying fastest
+            let mut axis = 0;
+            while axis < slice.len() && slice[axis] == ranges[axis].1 {
+                slice[axis] = ranges[axis].0;
+                axis += 1;
+            }
+            if axis == slice.len() {
+                break;
+            }
+            slice[axis] += 1;
         }
-        write!(f, "{}", stres)
+        Ok(())
     }
 }
 
 impl GollyBoard {
-    pub fn get_minx(&self) -> isize {
-        self.map.keys().map(|&(_,_,_,x)| x).min().unwrap()
-    }
-    pub fn get_maxx(&self) -> isize {
-        self.map.keys().map(|&(_,_,_,x)| x).max().unwrap()
-    }
-    //
-    pub fn get_miny(&self) -> isize {
-        self.map.keys().map(|&(_,_,y,_)| y).min().unwrap()
-    }
-    pub fn get_maxy(&self) -> isize {
-        self.map.keys().map(|&(_,_,y,_)| y).max().unwrap()
-    }
-    //
-    pub fn get_minz(&self) -> isize {
-        self.map.keys().map(|&(_,z,_,_)| z).min().unwrap()
-    }
-    pub fn get_maxz(&self) -> isize {
-        self.map.keys().map(|&(_,z,_,_)| z).max().unwrap()
-    }
-    pub fn get_minw(&self) -> isize {
-        self.map.keys().map(|&(w,_,_,_)| w).min().unwrap()
-    }
-    pub fn get_maxw(&self) -> isize {
-        self.map.keys().map(|&(w,_,_,_)| w).max().unwrap()
-    }
-    //
-    pub fn get_at(&self, p: Position) -> bool {
-        matches!(self.map.get(&p), Some(true))
+    /// Whether the cell at `p` (ordered `x, y, z, w, ...`) is active
+    pub fn get_at(&self, p: &Position) -> bool {
+        self.grid.get(p)
     }
     pub fn set_changefunction(&mut self, f: ChoiceFunction) {
         self.changefunction = Some(f);
     }
 
-    pub fn set_visibility(&mut self, f: VisibilityFunction) {
-        self.visibilityfunction = Some(f);
-        //for &pos in self.map.keys() {
-            //let visible = self.visibilityfunction.unwrap()(&self.map, pos, self.width, self.height);
-            //self.visibility.insert(pos, visible);
-        //}
+    /// Embed the board in `dims` dimensions, the new axes being mirrored
+    /// around the initial slice
+    pub fn set_dimensions(&mut self, dims: usize) {
+        self.grid = self.grid.lift(dims);
     }
 
     pub fn step(&mut self) -> bool {
-        // Get a vec of all the indices that are gonna flip
-        // Get the list of all the positions that will update
-        //println!("These will be updated : {:?}", self.map.keys());
-        let mut op_stack = self.map.keys()
-            .copied().collect::<Vec<Position>>();
-        // A hashmap to contain all the positions of living things after
-        // this current step
-        let mut newmap: HashMap<Position, bool> = HashMap::new();
-        let mut done: HashSet<Position> = HashSet::new();
-        while !op_stack.is_empty() {
-            let (w,z,y,x) = op_stack.pop().unwrap();
-            if done.contains(&(w,z,y,x)) {
-                continue;
-            }
-            //println!("{:?}", (z,y,x));
-            let neighbors = self.visibilityfunction.unwrap()((w,z,y,x));
-            //println!("{:?}", neighbors);
-            let (free, busy) = neighbors.iter()
-                .fold((0,0), |(f, b), &(wi,zi,yi,xi)| {
-                    let zi = isize::abs(zi);
-                    let wi = isize::abs(wi);
-                    if (wi,zi,yi,xi) == (w,z,y,x) {
-                        (f,b)
-                    } else if self.get_at((wi,zi,yi,xi)) {
-                        (f, b+1)
-                    } else {
-                        (f+1, b)
-                    }
-                }
-            );
-            //println!("(free={}, busy={})", free, busy);
-            if self.changefunction
-                .unwrap()(free, busy,self.get_at((w,z,y,x))) {
-                newmap.insert((w,z,y,x), true);
-            }
-            // Add to "done" hashset
-            done.insert((w,z,y,x));
-            // Add untreated neighbors to pile if it was an active
-            if self.get_at((w,z,y,x)) {
-                op_stack.extend(neighbors.iter()
-                            .filter(|x| !done.contains(&x)));
-            }
-        }
-        self.map.clear();
-        self.map.extend(newmap);
+        // Tabulate the change function by number of active cells in the
+        // whole neighbourhood, the cell itself included
+        let change = self.changefunction.unwrap();
+        let neighbours = 3_usize.pow(self.grid.dims as u32) - 1;
+        let born = (0..=neighbours)
+            .map(|t| change(neighbours - t, t, false))
+            .collect::<Vec<bool>>();
+        let survive = (0..=neighbours + 1)
+            .map(|t| t > 0 && change(neighbours + 1 - t, t - 1, true))
+            .collect::<Vec<bool>>();
+        self.grid.step(&survive, &born);
         true
     }
 
     pub fn seats_busy(&self) -> usize {
-        self.map.iter()
-            .filter_map(|(&(w,z,_,_), v)| match (v,z,w) {
-                (false,_,_) => None,
-                (true,0,0) => Some(1),
-                (true,_,0) | (true,0,_) => Some(2),
-                (true,_,_) => Some(4)
-            }).sum::<usize>()
+        self.grid.population()
     }
 }
 
@@ -185,37 +147,24 @@ fn main() {
     }
     let board = tmp.unwrap().parse::<GollyBoard>().unwrap();
     println!("{:?}", sol1(board.clone()));
-    println!("{:?}", sol2(board));
+    println!("{:?}", sol2(board.clone()));
+    // `day17 <dir>` also dumps the six cycles of part 2 as slice sheets
+    if let Some(dir) = std::env::args().nth(1) {
+        let mut run = board;
+        run.set_changefunction(CONWAY);
+        run.set_dimensions(4);
+        if let Err(e) = export_run(run, 6, 4, &dir) {
+            eprintln!("Export failed: {}", e);
+        }
+    }
 }
 
 /// # Errors
 ///
 /// Returns () for the sake of brevity
 fn sol1(mut data: GollyBoard) -> Result<usize,()> {
-    let visibilin: VisibilityFunction = |(_,z,y,x)| {
-        let mut ans = Vec::new();
-        for dx in -1..=1 {
-            for dy in -1..=1 {
-                for dz in -1..=1 {
-                    // Don't exclude (0,0,0)
-                    // since this is used to compute who's gonna update
-                    ans.push((0,isize::abs(z+dz), y+dy, x+dx));
-                }
-            }
-        }
-        ans
-    };
-    let choicelin: ChoiceFunction = |_, busy, oldstate| {
-        if oldstate {
-            busy == 2 || busy == 3
-        } else {
-            busy == 3
-        }
-    };
-    data.set_changefunction(choicelin);
-    //println!("Change function introduced");
-    data.set_visibility(visibilin);
-    //println!("Visibility computed");
+    data.set_changefunction(CONWAY);
+    data.set_dimensions(3);
     for _ in 0..6 {
         data.step();
     }
@@ -226,37 +175,70 @@ fn sol1(mut data: GollyBoard) -> Result<usize,()> {
 ///
 /// Returns () for the sake of brevity
 fn sol2(mut data: GollyBoard) -> Result<usize,()> {
-    let visibilin: VisibilityFunction = |(w,z,y,x)| {
-        let mut ans = Vec::new();
-        for dx in -1..=1 {
-            for dy in -1..=1 {
-                for dz in -1..=1 {
-                    for dw in -1..=1 {
-                        // Don't exclude (0,0,0,0)
-                        // since this is used to compute who's gonna update
-                        ans.push((isize::abs(w+dw),isize::abs(z+dz),
-                                y+dy,x+dx));    
-                    }
-                }
-            }
-        }
-        ans
-    };
-    let choicelin: ChoiceFunction = |_, busy, oldstate| {
-        if oldstate {
-            busy == 2 || busy == 3
-        } else {
-            busy == 3
-        }
-    };
-    data.set_changefunction(choicelin);
-    //println!("Change function introduced");
-    data.set_visibility(visibilin);
-    //println!("Visibility computed");
+    data.set_changefunction(CONWAY);
+    data.set_dimensions(4);
     for _ in 0..6 {
         data.step();
     }
     Ok(data.seats_busy())
 }
 
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const EXAMPLE: &str = ".#.\n..#\n###";
 
+    #[test]
+    fn example_part_1() {
+        let board = EXAMPLE.parse::<GollyBoard>().unwrap();
+        assert_eq!(sol1(board), Ok(112));
+    }
+    #[test]
+    fn example_part_2() {
+        let board = EXAMPLE.parse::<GollyBoard>().unwrap();
+        assert_eq!(sol2(board), Ok(848));
+    }
+    #[test]
+    fn mirrored_reads() {
+        let mut board = EXAMPLE.parse::<GollyBoard>().unwrap();
+        board.set_changefunction(|_, busy, old| busy == 3 || (old && busy == 2));
+        board.set_dimensions(3);
+        board.step();
+        assert!(board.get_at(&[0, 1, -1]));
+        assert_eq!(board.get_at(&[0, 1, -1]), board.get_at(&[0, 1, 1]));
+        assert_eq!(board.seats_busy(), 11);
+    }
+    #[test]
+    fn display_slices() {
+        let mut board = EXAMPLE.parse::<GollyBoard>().unwrap();
+        assert_eq!(board.to_string(), "\nz=0, w=0\n.#.\n..#\n###");
+        board.set_changefunction(|_, busy, old| busy == 3 || (old && busy == 2));
+        board.set_dimensions(3);
+        board.step();
+        assert_eq!(board.to_string(), "\nz=-1, w=0\n#..\n..#\n.#.\
+                                       \nz=0, w=0\n#.#\n.##\n.#.\
+                                       \n\nz=1, w=0\n#..\n..#\n.#.");
+    }
+    #[test]
+    fn contact_sheet_layout() {
+        let mut board = EXAMPLE.parse::<GollyBoard>().unwrap();
+        board.set_changefunction(CONWAY);
+        board.set_dimensions(4);
+        board.step();
+        let sheet = contact_sheet(&board, 1);
+        let mut lines = sheet.lines();
+        assert_eq!(lines.next(), Some("P1"));
+        assert_eq!(lines.next(),
+            Some("# tiles x=-1..3 y=0..4; columns z=-1..1; rows w=-1..1"));
+        // Three 5×5 tiles and their frames in each direction
+        assert_eq!(lines.next(), Some("19 19"));
+        assert_eq!(all_active_cells(&board).len(), board.seats_busy());
+    }
+    #[test]
+    fn generation_dump() {
+        let board = EXAMPLE.parse::<GollyBoard>().unwrap();
+        assert_eq!(generation_json(&board, 0),
+            "{\"generation\":0,\"axes\":[\"x\",\"y\"],\"active\":[[0,2],[1,0],[1,2],[2,1],[2,2]]}");
+    }
+}
@@ -0,0 +1,105 @@
+/// A range of byte offsets in the parsed line, end excluded
+#[derive(Clone,Copy,Eq,PartialEq,Debug)]
+struct Span {
+    start: usize,
+    end: usize
+}
+
+impl Span {
+    fn new(start: usize, end: usize) -> Self {
+        Span { start, end }
+    }
+
+    /// The smallest span covering both spans
+    fn to(self, other: Span) -> Span {
+        Span::new(self.start.min(other.start), self.end.max(other.end))
+    }
+}
+
+#[derive(Clone,Eq,PartialEq,Debug)]
+enum ExprKind {
+    Num(i64),
+    Var(String),
+    Unary(Operator, Box<Expr>),
+    Binary(Operator, Box<Expr>, Box<Expr>)
+}
+
+/// A node of an expression tree, and the part of the line it comes from
+///
+/// Parentheses do not get nodes of their own; the span of a parenthesized
+/// expression includes them, though.
+#[derive(Clone,Eq,PartialEq,Debug)]
+struct Expr {
+    kind: ExprKind,
+    span: Span
+}
+
+impl Expr {
+    /// Postfix tokens computing this expression, variables being replaced
+    /// by their value in `vars`
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`ParseError`] pointing at the first unknown variable.
+    fn postfix(&self, vars: &HashMap<String, i64>) -> Result<Vec<Token>, ParseError> {
+        let mut res = Vec::new();
+        self.push_postfix(vars, &mut res)?;
+        Ok(res)
+    }
+
+    fn push_postfix(&self, vars: &HashMap<String, i64>, out: &mut Vec<Token>)
+        -> Result<(), ParseError> {
+        match &self.kind {
+            ExprKind::Num(u) => out.push(Token::Num(*u)),
+            ExprKind::Var(name) => match vars.get(name) {
+                Some(&u) => out.push(Token::Num(u)),
+                None => return Err(ParseError {
+                    kind: ParseErrorKind::UnknownVariable, span: self.span
+                })
+            },
+            ExprKind::Unary(op, operand) => {
+                operand.push_postfix(vars, out)?;
+                out.push(Token::Op(*op));
+            },
+            ExprKind::Binary(op, lhs, rhs) => {
+                lhs.push_postfix(vars, out)?;
+                rhs.push_postfix(vars, out)?;
+                out.push(Token::Op(*op));
+            }
+        }
+        Ok(())
+    }
+
+    /// Write the expression with every operation but the outermost one
+    /// wrapped in parentheses
+    fn write_nested(&self, f: &mut std::fmt::Formatter<'_>, outer: bool) -> std::fmt::Result {
+        match &self.kind {
+            ExprKind::Num(u) => write!(f, "{}", u),
+            ExprKind::Var(name) => write!(f, "{}", name),
+            ExprKind::Unary(op, operand) => {
+                if !outer { write!(f, "(")?; }
+                write!(f, "{}", op.symbol())?;
+                operand.write_nested(f, false)?;
+                if !outer { write!(f, ")")?; }
+                Ok(())
+            },
+            ExprKind::Binary(op, lhs, rhs) => {
+                if !outer { write!(f, "(")?; }
+                lhs.write_nested(f, false)?;
+                write!(f, " {} ", op.symbol())?;
+                rhs.write_nested(f, false)?;
+                if !outer { write!(f, ")")?; }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Pretty-print the expression, making the grouping decided by the
+/// precedence table explicit : `1 + 2 * 3` parsed left to right shows as
+/// `(1 + 2) * 3`.
+impl std::fmt::Display for Expr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.write_nested(f, true)
+    }
+}
@@ -0,0 +1,235 @@
+//! Interactive calculator built on the day 18 evaluator
+//!
+//! Reads one expression per line and prints its value. Besides
+//! expressions, it understands :
+//!  - `let NAME = EXPR` : evaluate `EXPR` and remember it as `NAME`
+//!  - `:mode [part1|part2|normal]` : show or switch the operator precedence
+//!  - `:show EXPR` : print `EXPR` with its implicit parentheses
+//!  - `:vars` and `:history` : list the variables and previous inputs
+//!  - `:help` and `:quit`
+// The evaluator files are shared wholesale with the puzzle solver, which
+// uses parts of them the calculator does not need.
+#![allow(dead_code)]
+
+use std::io::prelude::*;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+include!("../ast.rs");
+include!("../error.rs");
+include!("../parser.rs");
+include!("../eval.rs");
+
+const HELP: &str = "\
+let NAME = EXPR     evaluate EXPR and store it as NAME
+:mode [MODE]        show or set the precedence mode (part1, part2, normal)
+:show EXPR          print EXPR with its implicit parentheses
+:vars               list variables
+:history            list previous inputs
+:help               show this message
+:quit               leave";
+
+/// Operator precedence rules the calculator can follow
+#[derive(Clone,Copy,Eq,PartialEq,Debug)]
+enum Mode {
+    Part1,
+    Part2,
+    Normal
+}
+
+impl Mode {
+    fn from_name(name: &str) -> Option<Mode> {
+        match name {
+            "part1" | "1" => Some(Mode::Part1),
+            "part2" | "2" => Some(Mode::Part2),
+            "normal" | "standard" => Some(Mode::Normal),
+            _ => None
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Mode::Part1 => "part1",
+            Mode::Part2 => "part2",
+            Mode::Normal => "normal"
+        }
+    }
+
+    fn table(self) -> PrecedenceTable {
+        match self {
+            Mode::Part1 => PrecedenceTable::left_to_right(),
+            Mode::Part2 => PrecedenceTable::addition_first(),
+            Mode::Normal => PrecedenceTable::standard()
+        }
+    }
+}
+
+/// What to do after an input line
+#[derive(Clone,Eq,PartialEq,Debug)]
+enum Reply {
+    Print(String),
+    Quit
+}
+
+/// The state of a calculator session
+struct Session {
+    mode: Mode,
+    table: PrecedenceTable,
+    vars: HashMap<String, i64>,
+    history: Vec<String>
+}
+
+/// Whether `name` can be used as a variable
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c.is_alphabetic() || c == '_')
+        && chars.all(|c| c.is_alphanumeric() || c == '_')
+}
+
+impl Session {
+    fn new() -> Self {
+        Session {
+            mode: Mode::Normal,
+            table: Mode::Normal.table(),
+            vars: HashMap::new(),
+            history: Vec::new()
+        }
+    }
+
+    /// Evaluate an expression, or describe why it cannot be
+    fn evaluate(&self, expr: &str) -> Result<i64, String> {
+        compute_with(expr, &self.table, &self.vars).map_err(|e| match e {
+            CalcError::Parse(e) => e.report(expr),
+            CalcError::Eval(e) => format!("error: {}", e)
+        })
+    }
+
+    /// Handle one line of input
+    fn handle(&mut self, input: &str) -> Reply {
+        let input = input.trim();
+        if input.is_empty() {
+            return Reply::Print(String::new());
+        }
+        if input != ":history" {
+            self.history.push(input.to_string());
+        }
+        let (command, rest) = match input.find(char::is_whitespace) {
+            Some(idx) => (&input[..idx], input[idx..].trim()),
+            None => (input, "")
+        };
+        Reply::Print(match command {
+            ":quit" | ":q" => return Reply::Quit,
+            ":help" => HELP.to_string(),
+            ":mode" if rest.is_empty() => format!("mode: {}", self.mode.name()),
+            ":mode" => match Mode::from_name(rest) {
+                Some(mode) => {
+                    self.mode = mode;
+                    self.table = mode.table();
+                    format!("mode: {}", mode.name())
+                },
+                None => format!("unknown mode {:?} (part1, part2, normal)", rest)
+            },
+            ":show" => match parse(rest, &self.table) {
+                Ok(expr) => expr.to_string(),
+                Err(e) => e.report(rest)
+            },
+            ":vars" => {
+                let mut vars = self.vars.iter()
+                    .map(|(name, value)| format!("{} = {}", name, value))
+                    .collect::<Vec<String>>();
+                vars.sort();
+                vars.join("\n")
+            },
+            ":history" => self.history.iter().enumerate()
+                .map(|(idx, line)| format!("{:>4}  {}", idx + 1, line))
+                .collect::<Vec<String>>()
+                .join("\n"),
+            "let" => match rest.split_once('=') {
+                Some((name, expr)) if is_identifier(name.trim()) => {
+                    let expr = expr.trim();
+                    match self.evaluate(expr) {
+                        Ok(value) => {
+                            self.vars.insert(name.trim().to_string(), value);
+                            format!("{} = {}", name.trim(), value)
+                        },
+                        Err(e) => e
+                    }
+                },
+                _ => "usage: let NAME = EXPR".to_string()
+            },
+            _ if command.starts_with(':') =>
+                format!("unknown command {} (try :help)", command),
+            _ => match self.evaluate(input) {
+                Ok(value) => value.to_string(),
+                Err(e) => e
+            }
+        })
+    }
+}
+
+fn main() {
+    let mut session = Session::new();
+    let stdin = std::io::stdin();
+    let mut lines = stdin.lock().lines();
+    loop {
+        print!("[{}]> ", session.mode.name());
+        if std::io::stdout().flush().is_err() {
+            break;
+        }
+        let line = match lines.next() {
+            Some(Ok(line)) => line,
+            _ => break
+        };
+        match session.handle(&line) {
+            Reply::Print(out) if out.is_empty() => (),
+            Reply::Print(out) => println!("{}", out),
+            Reply::Quit => break
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn print(s: &str) -> Reply {
+        Reply::Print(s.to_string())
+    }
+
+    #[test]
+    fn modes() {
+        let mut session = Session::new();
+        assert_eq!(session.handle("1 + 2 * 3"), print("7"));
+        assert_eq!(session.handle(":mode part1"), print("mode: part1"));
+        assert_eq!(session.handle("1 + 2 * 3"), print("9"));
+        assert_eq!(session.handle(":mode 2"), print("mode: part2"));
+        assert_eq!(session.handle("2 * 3 + (4 * 5)"), print("46"));
+        assert_eq!(session.handle(":show 2 * 3 + 4"), print("2 * (3 + 4)"));
+        assert_eq!(session.handle(":mode"), print("mode: part2"));
+        assert_eq!(session.handle(":mode weird"),
+            print("unknown mode \"weird\" (part1, part2, normal)"));
+    }
+
+    #[test]
+    fn variables() {
+        let mut session = Session::new();
+        assert_eq!(session.handle("let x = 4 * 5"), print("x = 20"));
+        assert_eq!(session.handle("let y_2 = x - 1"), print("y_2 = 19"));
+        assert_eq!(session.handle("x * y_2"), print("380"));
+        assert_eq!(session.handle(":vars"), print("x = 20\ny_2 = 19"));
+        assert_eq!(session.handle("let 3 = 4"), print("usage: let NAME = EXPR"));
+        assert_eq!(session.handle("z + 1"), print("z + 1\n^ unknown variable"));
+        assert_eq!(session.handle("x / 0"), print("error: division by zero"));
+    }
+
+    #[test]
+    fn history_and_commands() {
+        let mut session = Session::new();
+        session.handle("1 + 1");
+        session.handle("");
+        session.handle("let a = 2");
+        assert_eq!(session.handle(":history"), print("   1  1 + 1\n   2  let a = 2"));
+        assert_eq!(session.handle(":nope"), print("unknown command :nope (try :help)"));
+        assert_eq!(session.handle(":quit"), Reply::Quit);
+    }
+}
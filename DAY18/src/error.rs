@@ -0,0 +1,111 @@
+/// What went wrong while parsing an expression
+#[derive(Clone,Copy,Eq,PartialEq,Debug)]
+enum ParseErrorKind {
+    /// A character that is neither a digit, a letter, an operator nor a
+    /// parenthesis
+    UnknownSymbol(char),
+    /// A name with no value attached
+    UnknownVariable,
+    /// A `(` never closed, or a `)` never opened
+    UnbalancedParenthesis,
+    /// An operator, a `)` or the end of the line where an operand should be
+    MissingOperand,
+    /// Two operands in a row
+    MissingOperator,
+    /// An operator the precedence table has no entry for
+    NoPrecedence(Operator),
+    /// A literal that does not fit in an `i64`
+    NumberTooLarge
+}
+
+impl std::fmt::Display for ParseErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseErrorKind::UnknownSymbol(c) => write!(f, "unknown symbol {:?}", c),
+            ParseErrorKind::UnknownVariable => write!(f, "unknown variable"),
+            ParseErrorKind::UnbalancedParenthesis => write!(f, "unbalanced parenthesis"),
+            ParseErrorKind::MissingOperand => write!(f, "missing operand"),
+            ParseErrorKind::MissingOperator => write!(f, "missing operator"),
+            ParseErrorKind::NoPrecedence(op) =>
+                write!(f, "no precedence defined for {}", op.symbol()),
+            ParseErrorKind::NumberTooLarge => write!(f, "number too large")
+        }
+    }
+}
+
+/// A parsing error, and where it happened in the line
+#[derive(Clone,Copy,Eq,PartialEq,Debug)]
+struct ParseError {
+    kind: ParseErrorKind,
+    span: Span
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "column {}: {}", self.span.start + 1, self.kind)
+    }
+}
+
+impl ParseError {
+    /// Show the faulty line with the error underlined
+    fn report(&self, line: &str) -> String {
+        let width = (self.span.end - self.span.start).max(1);
+        format!("{}\n{}{} {}", line,
+            " ".repeat(self.span.start), "^".repeat(width), self.kind)
+    }
+}
+
+/// What went wrong while evaluating postfix tokens
+#[derive(Clone,Copy,Eq,PartialEq,Debug)]
+enum EvalError {
+    /// An operator came with too few operands on the stack
+    StackUnderflow,
+    /// Several values remained on the stack once every token was consumed
+    LeftoverOperands(usize),
+    /// The result of an operator does not fit in an `i64`
+    Overflow(Operator),
+    /// A division or remainder by zero
+    DivisionByZero,
+    /// A power with a negative exponent, which is not an integer
+    NegativeExponent
+}
+
+impl std::fmt::Display for EvalError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EvalError::StackUnderflow => write!(f, "operator without enough operands"),
+            EvalError::LeftoverOperands(n) => write!(f, "{} values left without operator", n),
+            EvalError::Overflow(op) => write!(f, "overflow in {}", op.symbol()),
+            EvalError::DivisionByZero => write!(f, "division by zero"),
+            EvalError::NegativeExponent => write!(f, "negative exponent")
+        }
+    }
+}
+
+/// Any error met while computing a line
+#[derive(Clone,Copy,Eq,PartialEq,Debug)]
+enum CalcError {
+    Parse(ParseError),
+    Eval(EvalError)
+}
+
+impl From<ParseError> for CalcError {
+    fn from(e: ParseError) -> Self {
+        CalcError::Parse(e)
+    }
+}
+
+impl From<EvalError> for CalcError {
+    fn from(e: EvalError) -> Self {
+        CalcError::Eval(e)
+    }
+}
+
+impl std::fmt::Display for CalcError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CalcError::Parse(e) => write!(f, "{}", e),
+            CalcError::Eval(e) => write!(f, "{}", e)
+        }
+    }
+}
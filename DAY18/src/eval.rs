@@ -0,0 +1,57 @@
+#[derive(Clone,Eq,PartialEq,Debug)]
+enum Token {
+    Op(Operator),
+    Num(i64)
+}
+
+/// Evaluate postfix tokens
+///
+/// # Errors
+///
+/// Returns an [`EvalError`] when the tokens do not form exactly one
+/// expression.
+fn evaluate(tokens: Vec<Token>) -> Result<i64, EvalError> {
+    let mut stack: Vec<i64> = Vec::new();
+    for tok in tokens {
+        match tok {
+            Token::Op(Operator::Neg) => {
+                let operand = stack.pop().ok_or(EvalError::StackUnderflow)?;
+                stack.push(Operator::Neg.apply_unary(operand)?);
+            },
+            Token::Op(op) => {
+                // Pop two operands
+                let rhs = stack.pop().ok_or(EvalError::StackUnderflow)?;
+                let lhs = stack.pop().ok_or(EvalError::StackUnderflow)?;
+                stack.push(op.apply(lhs, rhs)?);
+            },
+            Token::Num(u) => {
+                stack.push(u);
+            }
+        }
+    }
+    match stack.len() {
+        1 => Ok(stack[0]),
+        0 => Err(EvalError::StackUnderflow),
+        n => Err(EvalError::LeftoverOperands(n))
+    }
+}
+
+/// Evaluate a single line
+///
+/// # Errors
+///
+/// Returns a [`CalcError`] if the line cannot be parsed or evaluated.
+fn compute(line: &str, table: &PrecedenceTable) -> Result<i64, CalcError> {
+    compute_with(line, table, &HashMap::new())
+}
+
+/// Evaluate a single line, where names refer to `vars`
+///
+/// # Errors
+///
+/// Returns a [`CalcError`] if the line cannot be parsed or evaluated, or
+/// uses an unknown variable.
+fn compute_with(line: &str, table: &PrecedenceTable, vars: &HashMap<String, i64>)
+    -> Result<i64, CalcError> {
+    Ok(evaluate(build_postfix(line, table, vars)?)?)
+}
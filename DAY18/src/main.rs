@@ -1,5 +1,12 @@
 use std::fs::File;
 use std::io::prelude::*;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+include!("ast.rs");
+include!("error.rs");
+include!("parser.rs");
+include!("eval.rs");
 
 fn read_data(filepath: &str) -> std::io::Result<String> {
     let mut file = File::open(filepath)?;
@@ -8,102 +15,23 @@ fn read_data(filepath: &str) -> std::io::Result<String> {
     Ok(contents.trim().to_string())
 }
 
-#[derive(Clone,Eq,PartialEq,Debug)]
-enum Token {
-    Op(bool),
-    Num(usize)
-}
-
-fn evaluate(tokens: Vec<Token>) -> usize {
-    let mut stack: Vec<usize> = Vec::new();
-    for tok in tokens {
-        match tok {
-            Token::Op(k) => {
-                // Pop two operands
-                let a = stack.pop().unwrap();
-                let b = stack.pop().unwrap();
-                stack.push(if k { a+b } else { a*b });    
-            },
-            Token::Num(u) => {
-                stack.push(u);
-            }
-        }
-        //println!("STACK=:={:?}", stack);
-    }
-    stack.pop().unwrap()
-}
-
-fn build_postfix(pre_tokens: &mut Vec<&str>) -> Vec<Token> {
-    let mut res: Vec<Token> = Vec::new();
-    // As long as there as tokens… parse
-    while !pre_tokens.is_empty() {
-        // Pop a preliminary token from the stack
-        let pre_token = pre_tokens.pop().unwrap();
-        // Try integers
-        let preparse = pre_token.parse::<usize>();
-        if let Ok(parsed) = preparse {
-            // It's an int, push it
-            res.push(Token::Num(parsed));
-        } else if pre_token == "+" {
-            // Iterate and push
-            res.extend(build_postfix(pre_tokens));
-            res.push(Token::Op(true));
-        } else if pre_token == "*" {
-            // Iterate and push
-            res.extend(build_postfix(pre_tokens));
-            res.push(Token::Op(false));
-        } else if pre_token == ")" {
-            // Pop as long as we do not have the matching paren
-            let mut level = 1;
-            // Build a new list for that expression
-            let mut side_tokens = Vec::new();
-            while level > 0 && !pre_tokens.is_empty() {
-                let p = pre_tokens.pop().unwrap();
-                if p == ")" {
-                    level += 1;
-                } else if p == "(" {
-                    level -= 1;
-                }
-                if level > 0 {
-                    side_tokens.insert(0, p);
-                }
-            }
-            res.extend(build_postfix(&mut side_tokens));
-        }
-    }
-    res
+/// # Errors
+///
+/// Returns the [`CalcError`] of the first faulty line.
+fn solve(data: &str, table: &PrecedenceTable) -> Result<i64, CalcError> {
+    data.split('\n')
+        .try_fold(0_i64, |acc, line| {
+            let value = compute(line, table)?;
+            acc.checked_add(value).ok_or(CalcError::Eval(EvalError::Overflow(Operator::Add)))
+        })
 }
 
-fn sol2(data: &str) -> Result<usize, ()> {
-    // First, replace some stuff in the string
-    let data = data.replace("(", "( ( ");
-    let data = data.replace(")", " ) )");
-    let data = data.replace("*", ") * (");
-    // Then, split it into a vec of string refs
-    Ok(data.split('\n').map(|line| {
-        // Reverse in order to build the correct priorities
-        let line = format!("( {} )", line);
-        let mut pre_tokens = line.split(' ').collect::<Vec<&str>>();
-        // Build postfix eval
-        let tokens: Vec<Token> = build_postfix(&mut pre_tokens);
-        // Evaluate the postfix token chain
-        evaluate(tokens)
-    }).sum())
+fn sol2(data: &str) -> Result<i64, CalcError> {
+    solve(data, &PrecedenceTable::addition_first())
 }
 
-fn sol1(data: &str) -> Result<usize, ()> {
-    // First, replace some stuff in the string
-    let data = data.replace("(", "( ");
-    let data = data.replace(")", " )");
-    // Then, split it into a vec of string refs
-    Ok(data.split('\n').map(|line| {
-        // Reverse in order to build the correct priorities
-        let mut pre_tokens = line.split(' ').collect::<Vec<&str>>();
-        // Build postfix eval
-        let tokens: Vec<Token> = build_postfix(&mut pre_tokens);
-        // Evaluate the postfix token chain
-        evaluate(tokens)
-    }).sum())
+fn sol1(data: &str) -> Result<i64, CalcError> {
+    solve(data, &PrecedenceTable::left_to_right())
 }
 
 fn main() {
@@ -114,6 +42,12 @@ fn main() {
     let data = tmp.unwrap();
     println!("{:?}", sol1(&data));
     println!("{:?}", sol2(&data));
+    // Point at the faulty line, if any
+    for (idx, line) in data.split('\n').enumerate() {
+        if let Err(e) = parse(line, &PrecedenceTable::left_to_right()) {
+            eprintln!("line {}: {}", idx + 1, e.report(line));
+        }
+    }
 }
 
 #[cfg(test)]
@@ -165,5 +99,107 @@ mod test {
         let st = "1 + 2 * 3 + 4 * 5 + 6";
         assert_eq!(sol2(st), Ok(231));
     }
+
+    #[test]
+    fn test_standard_precedence() {
+        let table = PrecedenceTable::standard();
+        assert_eq!(solve("1 + 2 * 3 + 4 * 5 + 6", &table), Ok(33));
+        assert_eq!(solve("2 * (3 + 4) * 5", &table), Ok(70));
+    }
+
+    #[test]
+    fn test_table_postfix() {
+        use Operator::*;
+        let table = PrecedenceTable::new()
+            .with(Add, 1, Assoc::Right)
+            .with(Mul, 1, Assoc::Left);
+        // 1 + (2 + 3), but (1 * 2) * 3
+        assert_eq!(build_postfix("1 + 2 + 3", &table, &HashMap::new()),
+            Ok(vec![Token::Num(1), Token::Num(2), Token::Num(3), Token::Op(Add), Token::Op(Add)]));
+        assert_eq!(build_postfix("1 * 2 * 3", &table, &HashMap::new()),
+            Ok(vec![Token::Num(1), Token::Num(2), Token::Op(Mul), Token::Num(3), Token::Op(Mul)]));
+        assert_eq!(build_postfix("12*(3+4)", &PrecedenceTable::left_to_right(), &HashMap::new()),
+            Ok(vec![Token::Num(12), Token::Num(3), Token::Num(4), Token::Op(Add), Token::Op(Mul)]));
+    }
+
+    #[test]
+    fn test_pretty_print() {
+        let line = "1 + 2 * 3 + (4 * 5)";
+        let show = |table: &PrecedenceTable| parse(line, table).unwrap().to_string();
+        assert_eq!(show(&PrecedenceTable::left_to_right()), "((1 + 2) * 3) + (4 * 5)");
+        assert_eq!(show(&PrecedenceTable::addition_first()), "(1 + 2) * (3 + (4 * 5))");
+        assert_eq!(show(&PrecedenceTable::standard()), "(1 + (2 * 3)) + (4 * 5)");
+        let expr = parse(line, &PrecedenceTable::left_to_right()).unwrap();
+        assert_eq!(expr.span, Span::new(0, line.len()));
+        match expr.kind {
+            ExprKind::Binary(_, _, rhs) => assert_eq!(rhs.span, Span::new(12, 19)),
+            _ => panic!("Not a binary operation")
+        }
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let table = PrecedenceTable::left_to_right();
+        let kind = |line: &str| parse(line, &table).map_err(|e| (e.kind, e.span.start));
+        assert_eq!(kind("1 + (2 * 3"), Err((ParseErrorKind::UnbalancedParenthesis, 4)));
+        assert_eq!(kind("1 + 2) * 3"), Err((ParseErrorKind::UnbalancedParenthesis, 5)));
+        assert_eq!(kind("1 + * 3"), Err((ParseErrorKind::MissingOperand, 4)));
+        assert_eq!(kind("1 +"), Err((ParseErrorKind::MissingOperand, 3)));
+        assert_eq!(kind("()"), Err((ParseErrorKind::MissingOperand, 1)));
+        assert_eq!(kind("1 2"), Err((ParseErrorKind::MissingOperator, 2)));
+        assert_eq!(kind("(1 2)"), Err((ParseErrorKind::MissingOperator, 3)));
+        assert_eq!(kind("1 $ 2"), Err((ParseErrorKind::UnknownSymbol('$'), 2)));
+        assert_eq!(kind("99999999999999999999999"), Err((ParseErrorKind::NumberTooLarge, 0)));
+        assert_eq!(parse("1 - - - 2", &table).map(|e| e.to_string()), Ok("1 - (-(-2))".to_string()));
+        let table = PrecedenceTable::new().with(Operator::Add, 1, Assoc::Left);
+        assert_eq!(parse("1 * 2", &table).map_err(|e| e.kind),
+            Err(ParseErrorKind::NoPrecedence(Operator::Mul)));
+        let report = parse("1 + * 3", &PrecedenceTable::standard()).unwrap_err().report("1 + * 3");
+        assert_eq!(report, "1 + * 3\n    ^ missing operand");
+        assert!(matches!(sol1("1 + 2\n3 +"), Err(CalcError::Parse(_))));
+    }
+
+    #[test]
+    fn test_extended_operators() {
+        let std = PrecedenceTable::standard();
+        assert_eq!(solve("7 - 2 * 3", &std), Ok(1));
+        assert_eq!(solve("2 ^ 3 ^ 2", &std), Ok(512));
+        assert_eq!(solve("-2 ^ 2", &std), Ok(-4));
+        assert_eq!(solve("-7 / 2", &std), Ok(-3));
+        assert_eq!(solve("-7 % 2", &std), Ok(-1));
+        assert_eq!(solve("10 - 4 - 3", &std), Ok(3));
+        assert_eq!(solve("2 * -3", &std), Ok(-6));
+        // The puzzle's modes still apply
+        assert_eq!(sol1("1 - 2 * 3 ^ 2"), Ok(9));
+        assert_eq!(sol1("-2 ^ 2"), Ok(4));
+        assert_eq!(sol2("8 / 2 + 2 - 1"), Ok(2));
+        assert_eq!(sol2("2 * 3 ^ 2 - 1"), Ok(16));
+        let show = |line: &str| parse(line, &std).unwrap().to_string();
+        assert_eq!(show("-2 ^ 2 * 3"), "(-(2 ^ 2)) * 3");
+    }
+
+    #[test]
+    fn test_checked_arithmetic() {
+        let std = PrecedenceTable::standard();
+        let err = |line: &str| solve(line, &std);
+        assert_eq!(err("1 / 0"), Err(CalcError::Eval(EvalError::DivisionByZero)));
+        assert_eq!(err("1 % (2 - 2)"), Err(CalcError::Eval(EvalError::DivisionByZero)));
+        assert_eq!(err("2 ^ -1"), Err(CalcError::Eval(EvalError::NegativeExponent)));
+        assert_eq!(err("2 ^ 63"), Err(CalcError::Eval(EvalError::Overflow(Operator::Pow))));
+        assert_eq!(err("2 ^ 62 * 2"), Err(CalcError::Eval(EvalError::Overflow(Operator::Mul))));
+        assert_eq!(err("-(2 ^ 62) - 2 ^ 62"), Ok(i64::MIN));
+        assert_eq!(err("-(-(2 ^ 62) - 2 ^ 62)"), Err(CalcError::Eval(EvalError::Overflow(Operator::Neg))));
+        assert_eq!(err("-(2 ^ 62) * 2 / -1"), Err(CalcError::Eval(EvalError::Overflow(Operator::Div))));
+        assert_eq!(err("9223372036854775807 + 1"), Err(CalcError::Eval(EvalError::Overflow(Operator::Add))));
+    }
+
+    #[test]
+    fn test_eval_errors() {
+        use Operator::*;
+        assert_eq!(evaluate(vec![Token::Num(1), Token::Op(Add)]), Err(EvalError::StackUnderflow));
+        assert_eq!(evaluate(vec![Token::Num(1), Token::Num(2)]), Err(EvalError::LeftoverOperands(2)));
+        assert_eq!(evaluate(vec![]), Err(EvalError::StackUnderflow));
+        assert_eq!(evaluate(vec![Token::Num(3), Token::Num(2), Token::Op(Mul)]), Ok(6));
+    }
 }
 
@@ -0,0 +1,308 @@
+/// Operators understood by the evaluator
+///
+/// Every operator is binary, except for [`Operator::Neg`], the unary minus.
+/// Both share the `-` symbol; the parser tells them apart by position.
+#[derive(Clone,Copy,Eq,PartialEq,Hash,Debug)]
+enum Operator {
+    Add,
+    Sub,
+    Mul,
+    /// Integer division, rounding towards zero
+    Div,
+    /// Remainder of [`Operator::Div`], of the sign of the dividend
+    Rem,
+    Pow,
+    Neg
+}
+
+impl Operator {
+    /// The binary operator written `c`
+    fn from_symbol(c: char) -> Option<Operator> {
+        match c {
+            '+' => Some(Operator::Add),
+            '-' => Some(Operator::Sub),
+            '*' => Some(Operator::Mul),
+            '/' => Some(Operator::Div),
+            '%' => Some(Operator::Rem),
+            '^' => Some(Operator::Pow),
+            _ => None
+        }
+    }
+
+    fn symbol(self) -> char {
+        match self {
+            Operator::Add => '+',
+            Operator::Sub | Operator::Neg => '-',
+            Operator::Mul => '*',
+            Operator::Div => '/',
+            Operator::Rem => '%',
+            Operator::Pow => '^'
+        }
+    }
+
+    /// Apply a binary operator
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`EvalError`] on overflow, division by zero and negative
+    /// exponents rather than wrapping or panicking.
+    fn apply(self, a: i64, b: i64) -> Result<i64, EvalError> {
+        let res = match self {
+            Operator::Add => a.checked_add(b),
+            Operator::Sub => a.checked_sub(b),
+            Operator::Mul => a.checked_mul(b),
+            Operator::Div | Operator::Rem if b == 0 =>
+                return Err(EvalError::DivisionByZero),
+            Operator::Div => a.checked_div(b),
+            Operator::Rem => a.checked_rem(b),
+            Operator::Pow if b < 0 => return Err(EvalError::NegativeExponent),
+            Operator::Pow => u32::try_from(b).ok().and_then(|b| a.checked_pow(b)),
+            Operator::Neg => return self.apply_unary(b)
+        };
+        res.ok_or(EvalError::Overflow(self))
+    }
+
+    /// Apply the unary minus
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`EvalError`] when negating `i64::MIN`.
+    fn apply_unary(self, a: i64) -> Result<i64, EvalError> {
+        a.checked_neg().ok_or(EvalError::Overflow(self))
+    }
+}
+
+/// Which side an operator groups towards when chained with operators of
+/// the same precedence
+#[derive(Clone,Copy,Eq,PartialEq,Debug)]
+enum Assoc {
+    Left,
+    Right
+}
+
+/// Precedence and associativity of every operator
+///
+/// Higher precedences bind tighter. Operators missing from a table are
+/// not understood by parsers using it. The associativity of
+/// [`Operator::Neg`] is meaningless, only its precedence is used : it
+/// applies to everything to its right that binds tighter than itself.
+#[derive(Clone,Eq,PartialEq,Debug,Default)]
+struct PrecedenceTable {
+    entries: HashMap<Operator, (u8, Assoc)>
+}
+
+impl PrecedenceTable {
+    fn new() -> Self {
+        PrecedenceTable::default()
+    }
+
+    fn with(mut self, op: Operator, precedence: u8, assoc: Assoc) -> Self {
+        self.entries.insert(op, (precedence, assoc));
+        self
+    }
+
+    /// Part 1 : every binary operator is evaluated left to right, and
+    /// negation only applies to what immediately follows it
+    fn left_to_right() -> Self {
+        [Operator::Add, Operator::Sub, Operator::Mul,
+         Operator::Div, Operator::Rem, Operator::Pow].iter()
+            .fold(PrecedenceTable::new(), |t, &op| t.with(op, 1, Assoc::Left))
+            .with(Operator::Neg, 2, Assoc::Right)
+    }
+
+    /// Part 2 : additions and subtractions are evaluated before
+    /// multiplications, divisions and remainders
+    fn addition_first() -> Self {
+        PrecedenceTable::new()
+            .with(Operator::Add, 2, Assoc::Left)
+            .with(Operator::Sub, 2, Assoc::Left)
+            .with(Operator::Mul, 1, Assoc::Left)
+            .with(Operator::Div, 1, Assoc::Left)
+            .with(Operator::Rem, 1, Assoc::Left)
+            .with(Operator::Neg, 3, Assoc::Right)
+            .with(Operator::Pow, 4, Assoc::Right)
+    }
+
+    /// What the rest of the world uses, where `-2^2` is `-4`
+    #[allow(dead_code)]
+    fn standard() -> Self {
+        PrecedenceTable::new()
+            .with(Operator::Add, 1, Assoc::Left)
+            .with(Operator::Sub, 1, Assoc::Left)
+            .with(Operator::Mul, 2, Assoc::Left)
+            .with(Operator::Div, 2, Assoc::Left)
+            .with(Operator::Rem, 2, Assoc::Left)
+            .with(Operator::Neg, 3, Assoc::Right)
+            .with(Operator::Pow, 4, Assoc::Right)
+    }
+
+    /// Left and right binding powers of an operator. An operator continues
+    /// an expression when its left power is at least the current minimum,
+    /// and its right operand is parsed with its right power as minimum.
+    fn binding_power(&self, op: Operator) -> Option<(u16, u16)> {
+        self.entries.get(&op).map(|&(prec, assoc)| {
+            let base = 2 * u16::from(prec) + 2;
+            match assoc {
+                Assoc::Left => (base, base + 1),
+                Assoc::Right => (base + 1, base)
+            }
+        })
+    }
+}
+
+/// Lexical items of an expression
+#[derive(Clone,Copy,Eq,PartialEq,Debug)]
+enum Lexeme {
+    Num(i64),
+    /// A variable name, to be read from the line through its span
+    Ident,
+    Op(Operator),
+    Open,
+    Close
+}
+
+/// Split a line into lexemes, each with its span
+///
+/// # Errors
+///
+/// Returns a [`ParseError`] on the first unknown symbol or oversized literal.
+fn tokenize(line: &str) -> Result<Vec<(Lexeme, Span)>, ParseError> {
+    let mut res = Vec::new();
+    let mut chars = line.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        let single = Span::new(start, start + c.len_utf8());
+        if let Some(mut value) = c.to_digit(10).map(i64::from) {
+            let mut end = start + 1;
+            let mut overflow = false;
+            while let Some(&(idx, d)) = chars.peek() {
+                let d = match d.to_digit(10) { Some(d) => d, None => break };
+                match value.checked_mul(10).and_then(|v| v.checked_add(i64::from(d))) {
+                    Some(v) => value = v,
+                    None => overflow = true
+                }
+                end = idx + 1;
+                chars.next();
+            }
+            if overflow {
+                return Err(ParseError {
+                    kind: ParseErrorKind::NumberTooLarge, span: Span::new(start, end)
+                });
+            }
+            res.push((Lexeme::Num(value), Span::new(start, end)));
+        } else if c.is_alphabetic() || c == '_' {
+            let mut end = single.end;
+            while let Some(&(idx, d)) = chars.peek() {
+                if !(d.is_alphanumeric() || d == '_') {
+                    break;
+                }
+                end = idx + d.len_utf8();
+                chars.next();
+            }
+            res.push((Lexeme::Ident, Span::new(start, end)));
+        } else if let Some(op) = Operator::from_symbol(c) {
+            res.push((Lexeme::Op(op), single));
+        } else if c == '(' {
+            res.push((Lexeme::Open, single));
+        } else if c == ')' {
+            res.push((Lexeme::Close, single));
+        } else if !c.is_whitespace() {
+            return Err(ParseError { kind: ParseErrorKind::UnknownSymbol(c), span: single });
+        }
+    }
+    Ok(res)
+}
+
+/// Pratt parser building expression trees
+struct Parser<'a> {
+    line: &'a str,
+    lexemes: std::iter::Peekable<std::vec::IntoIter<(Lexeme, Span)>>,
+    table: &'a PrecedenceTable,
+    /// Where the line ends, for errors about missing things
+    eol: Span
+}
+
+impl<'a> Parser<'a> {
+    fn new(line: &'a str, table: &'a PrecedenceTable) -> Result<Self, ParseError> {
+        Ok(Parser {
+            line,
+            lexemes: tokenize(line)?.into_iter().peekable(),
+            table,
+            eol: Span::new(line.len(), line.len())
+        })
+    }
+
+    fn error(kind: ParseErrorKind, span: Span) -> ParseError {
+        ParseError { kind, span }
+    }
+
+    /// Parse an expression whose operators all bind at least as tight as
+    /// `min_power`
+    fn expression(&mut self, min_power: u16) -> Result<Expr, ParseError> {
+        // An operand first
+        let mut lhs = match self.lexemes.next() {
+            Some((Lexeme::Num(u), span)) => Expr { kind: ExprKind::Num(u), span },
+            Some((Lexeme::Ident, span)) => Expr {
+                kind: ExprKind::Var(self.line[span.start..span.end].to_string()), span
+            },
+            Some((Lexeme::Op(Operator::Sub), minus)) => {
+                let (_, right) = self.table.binding_power(Operator::Neg)
+                    .ok_or_else(|| Parser::error(ParseErrorKind::NoPrecedence(Operator::Neg), minus))?;
+                let operand = self.expression(right)?;
+                let span = minus.to(operand.span);
+                Expr { kind: ExprKind::Unary(Operator::Neg, Box::new(operand)), span }
+            },
+            Some((Lexeme::Open, open)) => {
+                let inner = self.expression(0)?;
+                match self.lexemes.next() {
+                    Some((Lexeme::Close, close)) => Expr { span: open.to(close), ..inner },
+                    Some((_, span)) =>
+                        return Err(Parser::error(ParseErrorKind::MissingOperator, span)),
+                    None =>
+                        return Err(Parser::error(ParseErrorKind::UnbalancedParenthesis, open))
+                }
+            },
+            Some((_, span)) => return Err(Parser::error(ParseErrorKind::MissingOperand, span)),
+            None => return Err(Parser::error(ParseErrorKind::MissingOperand, self.eol))
+        };
+        // Then as many operators as bind tightly enough
+        while let Some(&(Lexeme::Op(op), span)) = self.lexemes.peek() {
+            let (left, right) = self.table.binding_power(op)
+                .ok_or_else(|| Parser::error(ParseErrorKind::NoPrecedence(op), span))?;
+            if left < min_power {
+                break;
+            }
+            self.lexemes.next();
+            let rhs = self.expression(right)?;
+            let span = lhs.span.to(rhs.span);
+            lhs = Expr { kind: ExprKind::Binary(op, Box::new(lhs), Box::new(rhs)), span };
+        }
+        Ok(lhs)
+    }
+}
+
+/// Parse a whole line into an expression tree, following `table`
+///
+/// # Errors
+///
+/// Returns a [`ParseError`] locating the first problem in the line.
+fn parse(line: &str, table: &PrecedenceTable) -> Result<Expr, ParseError> {
+    let mut parser = Parser::new(line, table)?;
+    let expr = parser.expression(0)?;
+    match parser.lexemes.next() {
+        None => Ok(expr),
+        Some((Lexeme::Close, span)) =>
+            Err(Parser::error(ParseErrorKind::UnbalancedParenthesis, span)),
+        Some((_, span)) => Err(Parser::error(ParseErrorKind::MissingOperator, span))
+    }
+}
+
+/// Parse a whole line into postfix tokens, following `table`, with names
+/// replaced by their value in `vars`
+///
+/// # Errors
+///
+/// Returns a [`ParseError`] locating the first problem in the line.
+fn build_postfix(line: &str, table: &PrecedenceTable, vars: &HashMap<String, i64>)
+    -> Result<Vec<Token>, ParseError> {
+    parse(line, table)?.postfix(vars)
+}
@@ -0,0 +1,263 @@
+/// Right-hand side of a production in Chomsky normal form
+#[derive(Clone,Copy,Eq,PartialEq,Debug)]
+enum CnfBody {
+    Pair(usize, usize),
+    Literal(char)
+}
+
+/// A production in Chomsky normal form
+///
+/// Nonterminals are indices in [`CnfGrammar::origin`]. Unit productions
+/// (`8: 42`) do not exist in normal form, so the original rules they went
+/// through between `head` and `body` are kept in `chain`, outermost first,
+/// to rebuild faithful parse trees.
+#[derive(Clone,Eq,PartialEq,Debug)]
+struct CnfProduction {
+    head: usize,
+    chain: Vec<usize>,
+    body: CnfBody
+}
+
+/// Why a language cannot be put in Chomsky normal form
+#[derive(Clone,Copy,Eq,PartialEq,Debug)]
+enum CnfError {
+    /// The rule has an alternative matching the empty message
+    EmptyAlternative(usize),
+    /// The rule can rewrite into itself through unit productions alone,
+    /// giving infinitely many parse trees
+    UnitCycle(usize)
+}
+
+/// A [`Language`] in Chomsky normal form
+///
+/// Every original rule gets a nonterminal, and so do the helpers created
+/// to split long sequences and isolate literals inside sequences. Helpers
+/// have no origin, and are flattened away in parse trees.
+struct CnfGrammar {
+    origin: Vec<Option<usize>>,
+    index: HashMap<usize, usize>,
+    productions: Vec<CnfProduction>,
+    by_pair: HashMap<(usize, usize), Vec<usize>>,
+    by_literal: HashMap<char, Vec<usize>>
+}
+
+/// How a message matches a rule
+///
+/// Nodes are original rules, with the span of the message they match
+/// (end excluded). Leaves are the literals, with their position.
+#[derive(Clone,Eq,PartialEq,Debug)]
+enum ParseTree {
+    Node { rule: usize, span: (usize, usize), children: Vec<ParseTree> },
+    Literal { literal: char, position: usize }
+}
+
+/// Trees are shown as `(rule children...)`, literals as `'c'@position`
+impl std::fmt::Display for ParseTree {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseTree::Literal { literal, position } => write!(f, "'{}'@{}", literal, position),
+            ParseTree::Node { rule, children, .. } => {
+                write!(f, "({}", rule)?;
+                for child in children {
+                    write!(f, " {}", child)?;
+                }
+                write!(f, ")")
+            }
+        }
+    }
+}
+
+/// Number of parse trees of every nonterminal, for every substring : the
+/// cell `[i][l - 1]` covers the `l` characters starting at `i`
+type CykTable = Vec<Vec<HashMap<usize, u128>>>;
+
+impl LexicalAnalyzer {
+    /// Convert the language to Chomsky normal form
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`CnfError`] when a rule can match the empty message, or
+    /// loops on itself through unit productions.
+    fn to_cnf(&self) -> Result<CnfGrammar, CnfError> {
+        let mut rules = self.language.keys().copied().collect::<Vec<usize>>();
+        // Undefined rules still get a nonterminal, without productions
+        rules.extend(self.language.values().flatten().flatten()
+            .filter_map(|tok| match tok { Token::Rule(r) => Some(*r), _ => None }));
+        rules.sort_unstable();
+        rules.dedup();
+        let mut grammar = CnfGrammar {
+            origin: rules.iter().map(|&r| Some(r)).collect(),
+            index: rules.iter().enumerate().map(|(i, &r)| (r, i)).collect(),
+            productions: Vec::new(),
+            by_pair: HashMap::new(),
+            by_literal: HashMap::new()
+        };
+        // Ordered, so that the same unit cycle is reported on every run
+        let mut units: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+        let mut literal_helpers: HashMap<char, usize> = HashMap::new();
+        let mut direct: Vec<CnfProduction> = Vec::new();
+        let mut sorted = self.language.iter().collect::<Vec<(&usize, &Rule)>>();
+        sorted.sort_unstable_by_key(|(num, _)| **num);
+        for (num, rule) in sorted {
+            let head = grammar.index[num];
+            for seq in rule {
+                match seq.as_slice() {
+                    [] => return Err(CnfError::EmptyAlternative(*num)),
+                    [Token::Literal(c)] =>
+                        direct.push(CnfProduction { head, chain: vec![], body: CnfBody::Literal(*c) }),
+                    [Token::Rule(r)] => units.entry(head).or_default().push(grammar.index[r]),
+                    _ => {
+                        let symbols = seq.iter().map(|tok| match tok {
+                            Token::Rule(r) => grammar.index[r],
+                            Token::Literal(c) => *literal_helpers.entry(*c).or_insert_with(|| {
+                                grammar.origin.push(None);
+                                let helper = grammar.origin.len() - 1;
+                                direct.push(CnfProduction {
+                                    head: helper, chain: vec![], body: CnfBody::Literal(*c)
+                                });
+                                helper
+                            })
+                        }).collect::<Vec<usize>>();
+                        // X1 X2 ... Xn becomes X1 H1, H1 -> X2 H2, ...
+                        let mut current = head;
+                        for &symbol in &symbols[..symbols.len() - 2] {
+                            grammar.origin.push(None);
+                            let helper = grammar.origin.len() - 1;
+                            direct.push(CnfProduction {
+                                head: current, chain: vec![], body: CnfBody::Pair(symbol, helper)
+                            });
+                            current = helper;
+                        }
+                        let n = symbols.len();
+                        direct.push(CnfProduction {
+                            head: current, chain: vec![], body: CnfBody::Pair(symbols[n - 2], symbols[n - 1])
+                        });
+                    }
+                }
+            }
+        }
+        // Every unit path A -> S1 -> ... -> Sk gives A the productions of Sk
+        let mut bodies: HashMap<usize, Vec<CnfBody>> = HashMap::new();
+        for prod in &direct {
+            bodies.entry(prod.head).or_default().push(prod.body);
+        }
+        let mut productions = direct.clone();
+        for &head in units.keys() {
+            let mut stack = vec![vec![head]];
+            while let Some(path) = stack.pop() {
+                let last = *path.last().unwrap();
+                for &next in units.get(&last).into_iter().flatten() {
+                    if next == head {
+                        return Err(CnfError::UnitCycle(grammar.origin[head].unwrap()));
+                    }
+                    if path.contains(&next) {
+                        // A cycle not through `head`, reported from its own rules
+                        continue;
+                    }
+                    let mut longer = path.clone();
+                    longer.push(next);
+                    for body in bodies.get(&next).into_iter().flatten() {
+                        productions.push(CnfProduction { head, chain: longer[1..].to_vec(), body: *body });
+                    }
+                    stack.push(longer);
+                }
+            }
+        }
+        for (i, prod) in productions.iter().enumerate() {
+            match prod.body {
+                CnfBody::Pair(b, c) => grammar.by_pair.entry((b, c)).or_default().push(i),
+                CnfBody::Literal(c) => grammar.by_literal.entry(c).or_default().push(i)
+            }
+        }
+        grammar.productions = productions;
+        Ok(grammar)
+    }
+}
+
+impl CnfGrammar {
+    /// Fill the CYK table of a message
+    fn cyk_table(&self, chars: &[char]) -> CykTable {
+        let n = chars.len();
+        let mut table: CykTable = vec![vec![HashMap::new(); n]; n];
+        for (i, c) in chars.iter().enumerate() {
+            for &p in self.by_literal.get(c).into_iter().flatten() {
+                let count = table[i][0].entry(self.productions[p].head).or_insert(0);
+                *count = count.saturating_add(1);
+            }
+        }
+        for len in 2..=n {
+            for i in 0..=n - len {
+                let mut cell: HashMap<usize, u128> = HashMap::new();
+                for split in 1..len {
+                    let left = &table[i][split - 1];
+                    let right = &table[i + split][len - split - 1];
+                    for (&b, &nb) in left {
+                        for (&c, &nc) in right {
+                            for &p in self.by_pair.get(&(b, c)).into_iter().flatten() {
+                                let count = cell.entry(self.productions[p].head).or_insert(0);
+                                *count = count.saturating_add(nb.saturating_mul(nc));
+                            }
+                        }
+                    }
+                }
+                table[i][len - 1] = cell;
+            }
+        }
+        table
+    }
+
+    /// Number of distinct parse trees of `data` for rule `start`,
+    /// saturating at `u128::MAX`. More than one means the grammar is
+    /// ambiguous.
+    fn count_parses(&self, data: &str, start: usize) -> u128 {
+        let chars = data.chars().collect::<Vec<char>>();
+        match (chars.is_empty(), self.index.get(&start)) {
+            (false, Some(s)) => self.cyk_table(&chars)[0][chars.len() - 1]
+                .get(s).copied().unwrap_or(0),
+            _ => 0
+        }
+    }
+
+    /// One parse tree of `data` for rule `start`, if it matches
+    fn parse(&self, data: &str, start: usize) -> Option<ParseTree> {
+        let chars = data.chars().collect::<Vec<char>>();
+        if chars.is_empty() {
+            return None;
+        }
+        let s = *self.index.get(&start)?;
+        let table = self.cyk_table(&chars);
+        table[0][chars.len() - 1].get(&s)?;
+        self.build_tree(&table, &chars, s, 0, chars.len()).pop()
+    }
+
+    /// Trees for nonterminal `nt` matching `len` characters from `start`;
+    /// a single node for original rules, the spliced children for helpers
+    fn build_tree(&self, table: &CykTable, chars: &[char], nt: usize, start: usize, len: usize)
+        -> Vec<ParseTree> {
+        let has = |i: usize, l: usize, x: usize| table[i][l - 1].contains_key(&x);
+        let (prod, children) = self.productions.iter()
+            .filter(|p| p.head == nt)
+            .find_map(|p| match p.body {
+                CnfBody::Literal(c) if len == 1 && chars[start] == c =>
+                    Some((p, vec![ParseTree::Literal { literal: c, position: start }])),
+                CnfBody::Pair(b, c) if len > 1 => (1..len)
+                    .find(|&k| has(start, k, b) && has(start + k, len - k, c))
+                    .map(|k| {
+                        let mut children = self.build_tree(table, chars, b, start, k);
+                        children.extend(self.build_tree(table, chars, c, start + k, len - k));
+                        (p, children)
+                    }),
+                _ => None
+            })
+            .expect("CYK table inconsistent with its grammar");
+        let span = (start, start + len);
+        // Unit chains, innermost rule first
+        let children = prod.chain.iter().rev().fold(children, |children, &unit| {
+            vec![ParseTree::Node { rule: self.origin[unit].unwrap(), span, children }]
+        });
+        match self.origin[nt] {
+            Some(rule) => vec![ParseTree::Node { rule, span, children }],
+            None => children
+        }
+    }
+}
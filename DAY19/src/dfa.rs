@@ -0,0 +1,409 @@
+/// A deterministic automaton over the characters of messages
+///
+/// Missing transitions go to an implicit dead state. Once minimized,
+/// every state can still reach an accepting one.
+#[derive(Clone,Eq,PartialEq,Debug)]
+struct Dfa {
+    start: usize,
+    accepting: Vec<bool>,
+    transitions: Vec<BTreeMap<char, usize>>
+}
+
+/// A nondeterministic automaton, `None` labelling epsilon transitions,
+/// only used to glue automata together. It starts in state 0.
+#[derive(Default)]
+struct Nfa {
+    edges: Vec<Vec<(Option<char>, usize)>>,
+    accepting: Vec<bool>
+}
+
+impl Nfa {
+    fn add_state(&mut self) -> usize {
+        self.edges.push(Vec::new());
+        self.accepting.push(false);
+        self.edges.len() - 1
+    }
+
+    /// Copy a DFA in, returning its start and accepting states
+    fn embed(&mut self, dfa: &Dfa) -> (usize, Vec<usize>) {
+        let offset = self.edges.len();
+        for (state, transitions) in dfa.transitions.iter().enumerate() {
+            self.add_state();
+            self.edges[offset + state] = transitions.iter()
+                .map(|(&c, &to)| (Some(c), offset + to)).collect();
+        }
+        let accepting = (0..dfa.states()).filter(|&s| dfa.accepting[s])
+            .map(|s| offset + s).collect();
+        (offset + dfa.start, accepting)
+    }
+
+    /// States reachable from `states` through epsilon transitions alone
+    fn closure(&self, states: &mut Vec<usize>) {
+        let mut i = 0;
+        while i < states.len() {
+            for &(label, to) in &self.edges[states[i]] {
+                if label.is_none() && !states.contains(&to) {
+                    states.push(to);
+                }
+            }
+            i += 1;
+        }
+        states.sort_unstable();
+    }
+
+    /// Subset construction
+    fn determinize(&self) -> Dfa {
+        let mut first = vec![0];
+        self.closure(&mut first);
+        let mut index: HashMap<Vec<usize>, usize> = HashMap::new();
+        index.insert(first.clone(), 0);
+        let mut subsets = vec![first];
+        let mut dfa = Dfa { start: 0, accepting: Vec::new(), transitions: Vec::new() };
+        while dfa.states() < subsets.len() {
+            let subset = &subsets[dfa.states()];
+            let mut targets: BTreeMap<char, Vec<usize>> = BTreeMap::new();
+            for &state in subset {
+                for &(label, to) in &self.edges[state] {
+                    if let Some(c) = label {
+                        targets.entry(c).or_default().push(to);
+                    }
+                }
+            }
+            dfa.accepting.push(subset.iter().any(|&s| self.accepting[s]));
+            let mut transitions = BTreeMap::new();
+            for (c, mut target) in targets {
+                self.closure(&mut target);
+                target.dedup();
+                let next = index.len();
+                let id = *index.entry(target.clone()).or_insert(next);
+                if id == next {
+                    subsets.push(target);
+                }
+                transitions.insert(c, id);
+            }
+            dfa.transitions.push(transitions);
+        }
+        dfa
+    }
+}
+
+impl Dfa {
+    /// The automaton matching nothing, not even the empty message
+    fn nothing() -> Self {
+        Dfa { start: 0, accepting: vec![false], transitions: vec![BTreeMap::new()] }
+    }
+
+    fn literal(c: char) -> Self {
+        Dfa {
+            start: 0,
+            accepting: vec![false, true],
+            transitions: vec![std::iter::once((c, 1)).collect(), BTreeMap::new()]
+        }
+    }
+
+    fn states(&self) -> usize {
+        self.accepting.len()
+    }
+
+    /// The minimized automaton matching a message of every part in a row
+    fn concat(parts: &[Dfa]) -> Self {
+        let mut nfa = Nfa::default();
+        let mut ends = vec![nfa.add_state()];
+        for part in parts {
+            let (start, accepting) = nfa.embed(part);
+            for end in ends {
+                nfa.edges[end].push((None, start));
+            }
+            ends = accepting;
+        }
+        for end in ends {
+            nfa.accepting[end] = true;
+        }
+        nfa.determinize().minimize()
+    }
+
+    /// The minimized automaton matching a message of any of the parts
+    fn union(parts: &[Dfa]) -> Self {
+        let mut nfa = Nfa::default();
+        let start = nfa.add_state();
+        for part in parts {
+            let (part_start, accepting) = nfa.embed(part);
+            nfa.edges[start].push((None, part_start));
+            for end in accepting {
+                nfa.accepting[end] = true;
+            }
+        }
+        nfa.determinize().minimize()
+    }
+
+    /// Drop the states that cannot lead to acceptance, then merge the
+    /// equivalent ones by Moore's partition refinement
+    fn minimize(&self) -> Self {
+        // States from which an accepting state is reachable
+        let mut live = self.accepting.clone();
+        loop {
+            let mut changed = false;
+            for state in 0..self.states() {
+                if !live[state] && self.transitions[state].values().any(|&to| live[to]) {
+                    live[state] = true;
+                    changed = true;
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+        if !live[self.start] {
+            return Dfa::nothing();
+        }
+        let mut class = self.accepting.iter().map(|&a| usize::from(a)).collect::<Vec<usize>>();
+        let mut classes = 0;
+        loop {
+            let mut ids: HashMap<(usize, Vec<(char, usize)>), usize> = HashMap::new();
+            let refined = (0..self.states()).map(|state| {
+                let signature = (class[state], self.transitions[state].iter()
+                    .filter(|(_, &to)| live[to])
+                    .map(|(&c, &to)| (c, class[to])).collect());
+                let next = ids.len();
+                *ids.entry(signature).or_insert(next)
+            }).collect::<Vec<usize>>();
+            class = refined;
+            if ids.len() == classes {
+                break;
+            }
+            classes = ids.len();
+        }
+        // Renumber the live classes from the start, so that equal
+        // languages give equal automata
+        let mut number: HashMap<usize, usize> = HashMap::new();
+        let mut order = vec![self.start];
+        number.insert(class[self.start], 0);
+        let mut dfa = Dfa { start: 0, accepting: Vec::new(), transitions: Vec::new() };
+        while dfa.states() < order.len() {
+            let state = order[dfa.states()];
+            dfa.accepting.push(self.accepting[state]);
+            let mut transitions = BTreeMap::new();
+            for (&c, &to) in &self.transitions[state] {
+                if !live[to] {
+                    continue;
+                }
+                let next = number.len();
+                let id = *number.entry(class[to]).or_insert(next);
+                if id == next {
+                    order.push(to);
+                }
+                transitions.insert(c, id);
+            }
+            dfa.transitions.push(transitions);
+        }
+        dfa
+    }
+
+    /// Whether the automaton accepts the whole of `data`, in one pass
+    fn matches(&self, data: &str) -> bool {
+        let mut state = self.start;
+        for c in data.chars() {
+            match self.transitions[state].get(&c) {
+                Some(&next) => state = next,
+                None => return false
+            }
+        }
+        self.accepting[state]
+    }
+}
+
+/// Turns rules into automata and regular expressions, memoizing the rules
+/// that do not depend on recursion
+///
+/// A rule referring to itself, directly or not, is unrolled : once it
+/// appears `depth + 1` times in the current expansion, the alternative
+/// going one level deeper is dropped. `8: 42 | 42 8` with a depth of 2
+/// becomes `42 | 42 42 | 42 42 42`.
+struct RuleCompiler<'a> {
+    language: &'a Language,
+    depth: usize,
+    /// Rules whose expansion goes through a recursive rule, and so
+    /// depends on where it happens
+    unbounded: HashSet<usize>,
+    stack: Vec<usize>,
+    dfas: HashMap<usize, Dfa>,
+    regexes: HashMap<usize, Option<String>>
+}
+
+impl<'a> RuleCompiler<'a> {
+    fn new(language: &'a Language, depth: usize) -> Self {
+        let reach = reachable_rules(language);
+        let recursive = reach.iter().filter(|(r, seen)| seen.contains(r))
+            .map(|(&r, _)| r).collect::<HashSet<usize>>();
+        let unbounded = reach.iter()
+            .filter(|(r, seen)| recursive.contains(r) || !seen.is_disjoint(&recursive))
+            .map(|(&r, _)| r).collect();
+        RuleCompiler {
+            language, depth, unbounded,
+            stack: Vec::new(),
+            dfas: HashMap::new(),
+            regexes: HashMap::new()
+        }
+    }
+
+    /// Whether expanding `rule` here would unroll it too deep
+    fn too_deep(&self, rule: usize) -> bool {
+        self.stack.iter().filter(|&&r| r == rule).count() > self.depth
+    }
+
+    fn dfa(&mut self, rule: usize) -> Dfa {
+        if let Some(dfa) = self.dfas.get(&rule) {
+            return dfa.clone();
+        }
+        let language = self.language;
+        let alternatives = match language.get(&rule) {
+            Some(alternatives) if !self.too_deep(rule) => alternatives,
+            _ => return Dfa::nothing()
+        };
+        self.stack.push(rule);
+        let alternatives = alternatives.iter().map(|seq| {
+            let parts = seq.iter().map(|tok| match tok {
+                Token::Literal(c) => Dfa::literal(*c),
+                Token::Rule(r) => self.dfa(*r)
+            }).collect::<Vec<Dfa>>();
+            Dfa::concat(&parts)
+        }).collect::<Vec<Dfa>>();
+        self.stack.pop();
+        let dfa = Dfa::union(&alternatives);
+        if !self.unbounded.contains(&rule) {
+            self.dfas.insert(rule, dfa.clone());
+        }
+        dfa
+    }
+
+    /// A regular expression for `rule`, or `None` if it matches nothing
+    fn regex(&mut self, rule: usize) -> Option<String> {
+        if let Some(regex) = self.regexes.get(&rule) {
+            return regex.clone();
+        }
+        let language = self.language;
+        let alternatives = match language.get(&rule) {
+            Some(alternatives) if !self.too_deep(rule) => alternatives,
+            _ => return None
+        };
+        self.stack.push(rule);
+        let alternatives = alternatives.iter().filter_map(|seq| {
+            seq.iter().map(|tok| match tok {
+                Token::Literal(c) => Some(regex_escape(*c)),
+                Token::Rule(r) => self.regex(*r)
+            }).collect::<Option<String>>()
+        }).collect::<Vec<String>>();
+        self.stack.pop();
+        let regex = match alternatives.len() {
+            0 => None,
+            1 => alternatives.into_iter().next(),
+            _ => Some(format!("(?:{})", alternatives.join("|")))
+        };
+        if !self.unbounded.contains(&rule) {
+            self.regexes.insert(rule, regex.clone());
+        }
+        regex
+    }
+}
+
+/// The rules every rule can expand to, through any number of steps
+fn reachable_rules(language: &Language) -> HashMap<usize, HashSet<usize>> {
+    let referenced = |rule: &usize| language.get(rule).into_iter().flatten().flatten()
+        .filter_map(|tok| match tok { Token::Rule(r) => Some(*r), _ => None })
+        .collect::<Vec<usize>>();
+    let reachable = |from: usize| {
+        let mut seen: HashSet<usize> = HashSet::new();
+        let mut todo = referenced(&from);
+        while let Some(rule) = todo.pop() {
+            if seen.insert(rule) {
+                todo.extend(referenced(&rule));
+            }
+        }
+        seen
+    };
+    language.keys().map(|&r| (r, reachable(r))).collect()
+}
+
+/// A literal character, escaped if regular expressions give it a meaning
+fn regex_escape(c: char) -> String {
+    if "\\.+*?()|[]{}^$#&-~".contains(c) {
+        format!("\\{}", c)
+    } else {
+        c.to_string()
+    }
+}
+
+impl LexicalAnalyzer {
+    /// Minimized automaton matching rule 0, recursive rules being
+    /// unrolled `depth` times
+    fn compile_dfa(&self, depth: usize) -> Dfa {
+        RuleCompiler::new(&self.language, depth).dfa(0)
+    }
+
+    /// Regular expression matching rule 0, recursive rules being unrolled
+    /// `depth` times, or `None` if it matches nothing
+    fn compile_regex(&self, depth: usize) -> Option<String> {
+        RuleCompiler::new(&self.language, depth).regex(0)
+            .map(|regex| format!("^{}$", regex))
+    }
+
+    /// Length of the shortest message matched by every rule that matches
+    /// something
+    fn shortest_matches(&self) -> HashMap<usize, usize> {
+        let mut shortest: HashMap<usize, usize> = HashMap::new();
+        loop {
+            let mut changed = false;
+            for (&num, rule) in &self.language {
+                let best = rule.iter().filter_map(|seq| seq.iter().map(|tok| match tok {
+                    Token::Literal(_) => Some(1),
+                    Token::Rule(r) => shortest.get(r).copied()
+                }).sum::<Option<usize>>()).min();
+                if let Some(best) = best {
+                    if shortest.get(&num).is_none_or(|&old| best < old) {
+                        shortest.insert(num, best);
+                        changed = true;
+                    }
+                }
+            }
+            if !changed {
+                return shortest;
+            }
+        }
+    }
+
+    /// Unrolling depth sufficient for messages of up to `max_len`
+    /// characters
+    ///
+    /// A rule is on the stack once more for every turn of a recursion
+    /// cycle, and every turn goes through alternatives that match something
+    /// besides the rule going deeper. The fewest characters one turn takes
+    /// bounds the number of turns; cycles that can take none at all, like
+    /// unit cycles, are only bounded by one turn per character.
+    fn unrolling_depth(&self, max_len: usize) -> usize {
+        let shortest = self.shortest_matches();
+        let reach = reachable_rules(&self.language);
+        let in_cycle = |a: usize, b: usize| reach[&a].contains(&b)
+            && reach.get(&b).is_some_and(|seen| seen.contains(&a));
+        let mut step: Option<usize> = None;
+        for (&num, rule) in &self.language {
+            for seq in rule {
+                let lengths = seq.iter().map(|tok| match tok {
+                    Token::Literal(_) => Some(1),
+                    Token::Rule(r) => shortest.get(r).copied()
+                }).collect::<Option<Vec<usize>>>();
+                // Alternatives that match nothing are never part of a turn
+                let lengths = match lengths { Some(l) => l, None => continue };
+                let total = lengths.iter().sum::<usize>();
+                for (tok, len) in seq.iter().zip(&lengths) {
+                    if matches!(tok, Token::Rule(r) if in_cycle(num, *r)) {
+                        step = Some(step.map_or(total - len, |s| s.min(total - len)));
+                    }
+                }
+            }
+        }
+        match step {
+            Some(step) if step > 0 => max_len / step,
+            _ => max_len
+        }
+    }
+}
@@ -0,0 +1,108 @@
+/// An Earley item : alternative `alt` of rule `rule`, recognized up to
+/// (excluding) the token at `dot`, started at position `origin` in the
+/// message
+#[derive(Clone,Copy,Eq,PartialEq,Hash,Debug)]
+struct EarleyItem {
+    rule: usize,
+    alt: usize,
+    dot: usize,
+    origin: usize
+}
+
+/// One set of Earley items per position in the message, the last one
+/// being after the final character
+type EarleyChart = Vec<Vec<EarleyItem>>;
+
+impl LexicalAnalyzer {
+    /// Rules that can match the empty message
+    fn nullable_rules(&self) -> HashSet<usize> {
+        let mut nullable = HashSet::new();
+        loop {
+            let before = nullable.len();
+            for (&num, rule) in &self.language {
+                if rule.iter().any(|seq| seq.iter().all(|tok|
+                    matches!(tok, Token::Rule(r) if nullable.contains(r)))) {
+                    nullable.insert(num);
+                }
+            }
+            if nullable.len() == before {
+                return nullable;
+            }
+        }
+    }
+
+    /// The token after the dot of an item, if any
+    fn next_token(&self, item: &EarleyItem) -> Option<&Token> {
+        self.language.get(&item.rule)
+            .and_then(|rule| rule[item.alt].get(item.dot))
+    }
+
+    /// Build the Earley chart of `data` for rule `start`
+    ///
+    /// This works for any grammar, including left recursive ones and rules
+    /// matching the empty message. Undefined rules simply match nothing.
+    fn earley_chart(&self, data: &str, start: usize) -> EarleyChart {
+        let chars = data.chars().collect::<Vec<char>>();
+        let nullable = self.nullable_rules();
+        let mut chart: EarleyChart = vec![Vec::new(); chars.len() + 1];
+        let mut seen: Vec<HashSet<EarleyItem>> = vec![HashSet::new(); chars.len() + 1];
+        let add = |chart: &mut EarleyChart, seen: &mut Vec<HashSet<EarleyItem>>,
+                   k: usize, item: EarleyItem| {
+            if seen[k].insert(item) {
+                chart[k].push(item);
+            }
+        };
+        if let Some(rule) = self.language.get(&start) {
+            for alt in 0..rule.len() {
+                add(&mut chart, &mut seen, 0, EarleyItem { rule: start, alt, dot: 0, origin: 0 });
+            }
+        }
+        for k in 0..=chars.len() {
+            let mut i = 0;
+            while i < chart[k].len() {
+                let item = chart[k][i];
+                i += 1;
+                match self.next_token(&item) {
+                    // Prediction
+                    Some(Token::Rule(r)) => {
+                        if let Some(rule) = self.language.get(r) {
+                            for alt in 0..rule.len() {
+                                add(&mut chart, &mut seen, k,
+                                    EarleyItem { rule: *r, alt, dot: 0, origin: k });
+                            }
+                        }
+                        // Rules matching nothing can be skipped right away
+                        if nullable.contains(r) {
+                            add(&mut chart, &mut seen, k, EarleyItem { dot: item.dot + 1, ..item });
+                        }
+                    },
+                    // Scanning
+                    Some(Token::Literal(c)) => {
+                        if chars.get(k) == Some(c) {
+                            add(&mut chart, &mut seen, k + 1, EarleyItem { dot: item.dot + 1, ..item });
+                        }
+                    },
+                    // Completion
+                    None => {
+                        let parents = chart[item.origin].iter()
+                            .filter(|p| self.next_token(p) == Some(&Token::Rule(item.rule)))
+                            .copied()
+                            .collect::<Vec<EarleyItem>>();
+                        for parent in parents {
+                            add(&mut chart, &mut seen, k, EarleyItem { dot: parent.dot + 1, ..parent });
+                        }
+                    }
+                }
+            }
+        }
+        chart
+    }
+
+    /// Whether the whole of `data` matches rule 0, using an Earley
+    /// recognizer
+    fn matches_earley(&self, data: &str) -> bool {
+        let chart = self.earley_chart(data, 0);
+        chart[chart.len() - 1].iter().any(|item|
+            item.rule == 0 && item.origin == 0 && self.next_token(item).is_none())
+    }
+}
@@ -0,0 +1,132 @@
+/// How far a message got in matching a rule, and what stopped it
+#[derive(Clone,Eq,PartialEq,Debug)]
+struct Explanation {
+    message: String,
+    rule: usize,
+    matched: bool,
+    /// Number of characters that can start a matching message; all of
+    /// them when the message is only too short
+    furthest: usize,
+    /// Literals that could have come next at `furthest`
+    expected_literals: BTreeSet<char>,
+    /// Rules that could have started at `furthest`, inside rules already
+    /// under way
+    expected_rules: BTreeSet<usize>,
+    /// The deepest chain of nested rules at `furthest`, outermost first
+    rule_stack: Vec<usize>
+}
+
+impl std::fmt::Display for Explanation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{}", self.message)?;
+        if self.matched {
+            return write!(f, "matches rule {}", self.rule);
+        }
+        let len = self.message.chars().count();
+        let what = if self.furthest == len {
+            "message ends too early".to_string()
+        } else {
+            format!("no match past {} characters", self.furthest)
+        };
+        writeln!(f, "{}^ {}", " ".repeat(self.furthest), what)?;
+        let expected = self.expected_literals.iter().map(|c| format!("{:?}", c))
+            .chain(self.expected_rules.iter().map(|r| format!("rule {}", r)))
+            .collect::<Vec<String>>();
+        if expected.is_empty() {
+            writeln!(f, "expected: end of message")?;
+        } else {
+            writeln!(f, "expected: {}", expected.join(", "))?;
+        }
+        write!(f, "rule stack: {}", self.rule_stack.iter()
+            .map(usize::to_string).collect::<Vec<String>>().join(" > "))
+    }
+}
+
+impl LexicalAnalyzer {
+    /// Explain how `data` fares against rule `start`, from its Earley chart
+    fn explain(&self, data: &str, start: usize) -> Explanation {
+        let chart = self.earley_chart(data, start);
+        let len = chart.len() - 1;
+        let matched = chart[len].iter().any(|item|
+            item.rule == start && item.origin == 0 && self.next_token(item).is_none());
+        // Sets only get items while the message so far can start a match
+        let furthest = (0..=len).rev().find(|&k| !chart[k].is_empty()).unwrap_or(0);
+        let mut explanation = Explanation {
+            message: data.to_string(),
+            rule: start,
+            matched,
+            furthest,
+            expected_literals: BTreeSet::new(),
+            expected_rules: BTreeSet::new(),
+            rule_stack: Vec::new()
+        };
+        if matched {
+            return explanation;
+        }
+        let mut memo = HashMap::new();
+        for item in &chart[furthest] {
+            match self.next_token(item) {
+                Some(Token::Literal(c)) => {
+                    explanation.expected_literals.insert(*c);
+                    let (stack, _) = self.rule_stack(&chart, start, furthest, *item,
+                        &mut HashSet::new(), &mut memo);
+                    let stack = stack.unwrap_or_default();
+                    if stack.len() > explanation.rule_stack.len() {
+                        explanation.rule_stack = stack;
+                    }
+                },
+                Some(Token::Rule(r)) if item.origin < furthest || furthest == 0 => {
+                    explanation.expected_rules.insert(*r);
+                },
+                _ => ()
+            }
+        }
+        explanation
+    }
+
+    /// The longest chain of rules from `start` down to `item`, found in
+    /// set `k` of the chart, outermost first
+    ///
+    /// Items of a set can predict each other in loops; `on_path` keeps the
+    /// search from following them forever. The second value tells whether
+    /// a loop was cut below `item` : what was found then depends on the
+    /// path leading to `item`, so it is not kept in `memo`.
+    fn rule_stack(&self, chart: &EarleyChart, start: usize, k: usize, item: EarleyItem,
+                  on_path: &mut HashSet<(usize, EarleyItem)>,
+                  memo: &mut HashMap<(usize, EarleyItem), Option<Vec<usize>>>)
+        -> (Option<Vec<usize>>, bool)
+    {
+        if item.rule == start && item.origin == 0 {
+            return (Some(vec![start]), false);
+        }
+        if let Some(stack) = memo.get(&(k, item)) {
+            return (stack.clone(), false);
+        }
+        on_path.insert((k, item));
+        let parents = chart[item.origin].iter()
+            .filter(|p| self.next_token(p) == Some(&Token::Rule(item.rule)))
+            .copied()
+            .collect::<Vec<EarleyItem>>();
+        let mut best: Option<Vec<usize>> = None;
+        let mut cut = false;
+        for parent in parents {
+            if on_path.contains(&(item.origin, parent)) {
+                cut = true;
+                continue;
+            }
+            let (stack, parent_cut) = self.rule_stack(chart, start, item.origin, parent, on_path, memo);
+            cut |= parent_cut;
+            if let Some(stack) = stack {
+                if best.as_ref().is_none_or(|b| stack.len() > b.len()) {
+                    best = Some(stack);
+                }
+            }
+        }
+        on_path.remove(&(k, item));
+        let best = best.map(|mut stack| { stack.push(item.rule); stack });
+        if !cut {
+            memo.insert((k, item), best.clone());
+        }
+        (best, cut)
+    }
+}
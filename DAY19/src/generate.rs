@@ -0,0 +1,97 @@
+impl LexicalAnalyzer {
+    /// Every message of at most `max_len` characters matched by rule
+    /// `start`
+    ///
+    /// The sets of every rule grow together until none changes, which
+    /// terminates whatever the recursion since they are bounded by
+    /// `max_len`.
+    fn enumerate(&self, start: usize, max_len: usize) -> BTreeSet<String> {
+        let mut strings: HashMap<usize, BTreeSet<String>> = HashMap::new();
+        loop {
+            let mut changed = false;
+            for (&num, rule) in &self.language {
+                for seq in rule {
+                    let mut prefixes: BTreeSet<String> = std::iter::once(String::new()).collect();
+                    for tok in seq {
+                        let parts = match tok {
+                            Token::Literal(c) => std::iter::once(c.to_string()).collect(),
+                            Token::Rule(r) => strings.get(r).cloned().unwrap_or_default()
+                        };
+                        prefixes = prefixes.iter()
+                            .flat_map(|prefix| parts.iter().map(move |part| format!("{}{}", prefix, part)))
+                            .filter(|s| s.chars().count() <= max_len)
+                            .collect();
+                    }
+                    let known = strings.entry(num).or_default();
+                    for s in prefixes {
+                        changed |= known.insert(s);
+                    }
+                }
+            }
+            if !changed {
+                return strings.remove(&start).unwrap_or_default();
+            }
+        }
+    }
+
+    /// Smallest height of a derivation tree for every rule that matches
+    /// something, literals having height 0
+    fn derivation_heights(&self) -> HashMap<usize, usize> {
+        let mut heights: HashMap<usize, usize> = HashMap::new();
+        loop {
+            let mut changed = false;
+            for (&num, rule) in &self.language {
+                let best = rule.iter().filter_map(|seq| self.sequence_height(seq, &heights)).min();
+                if let Some(best) = best {
+                    if heights.get(&num).is_none_or(|&old| best < old) {
+                        heights.insert(num, best);
+                        changed = true;
+                    }
+                }
+            }
+            if !changed {
+                return heights;
+            }
+        }
+    }
+
+    /// Height of the smallest derivation of a sequence, if it has any
+    fn sequence_height(&self, seq: &[Token], heights: &HashMap<usize, usize>) -> Option<usize> {
+        seq.iter().map(|tok| match tok {
+            Token::Literal(_) => Some(0),
+            Token::Rule(r) => heights.get(r).copied()
+        }).try_fold(0, |acc, h| h.map(|h| acc.max(h))).map(|h| h + 1)
+    }
+
+    /// A random message matched by rule `start`, or `None` if it matches
+    /// nothing
+    ///
+    /// Alternatives are picked uniformly up to `depth_cap` nested rules;
+    /// deeper, the alternatives with the smallest derivations are the only
+    /// candidates, so that recursive rules come to an end.
+    fn sample(&self, rng: &mut XorShift, start: usize, depth_cap: usize) -> Option<String> {
+        let heights = self.derivation_heights();
+        heights.get(&start)?;
+        let mut res = String::new();
+        // Tokens left to expand, with their depth, last one first
+        let mut todo = vec![(Token::Rule(start), 0)];
+        while let Some((tok, depth)) = todo.pop() {
+            let num = match tok {
+                Token::Literal(c) => { res.push(c); continue; },
+                Token::Rule(num) => num
+            };
+            let candidates = self.language[&num].iter()
+                .filter_map(|seq| self.sequence_height(seq, &heights).map(|h| (seq, h)))
+                .collect::<Vec<(&Sequence, usize)>>();
+            let candidates = if depth < depth_cap {
+                candidates.into_iter().map(|(seq, _)| seq).collect::<Vec<&Sequence>>()
+            } else {
+                let lowest = heights[&num];
+                candidates.into_iter().filter(|&(_, h)| h == lowest).map(|(seq, _)| seq).collect()
+            };
+            let seq = candidates[rng.below(candidates.len())];
+            todo.extend(seq.iter().rev().map(|tok| (tok.clone(), depth + 1)));
+        }
+        Some(res)
+    }
+}
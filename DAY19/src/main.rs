@@ -1,6 +1,6 @@
 use std::fs::File;
 use std::io::prelude::*;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 
 fn read_data(filepath: &str) -> std::io::Result<String> {
     let mut file = File::open(filepath)?;
@@ -19,6 +19,14 @@ type Sequence = Vec<Token>;
 type Rule = Vec<Sequence>;
 type Language = HashMap<usize,Rule>;
 
+include!("earley.rs");
+include!("cyk.rs");
+include!("dfa.rs");
+include!("validate.rs");
+include!("xorshift.rs");
+include!("generate.rs");
+include!("explain.rs");
+
 struct LexicalAnalyzer {
     language: Language
 }
@@ -29,30 +37,42 @@ impl LexicalAnalyzer {
             language: Language::new()
         }
     }
-    fn inject_rules(&mut self, rules: &str) -> Result<usize,()>{
-        for rule in rules.split('\n') {
-            let mut rulesplit = rule.split(": ");
-            // Rule number
-            let rulenum = rulesplit.next().unwrap().parse::<usize>();
-            if rulenum.is_err() {
-                return Err(())
+    /// Add rules, one per line, replacing rules already defined; blank
+    /// lines are skipped
+    ///
+    /// # Errors
+    ///
+    /// Returns the [Diagnostic](Diagnostic) of the first malformed line,
+    /// numbered from 1. The lines before it are kept.
+    fn inject_rules(&mut self, rules: &str) -> Result<usize,Diagnostic>{
+        for (idx, rule) in rules.lines().enumerate() {
+            if rule.trim().is_empty() {
+                continue;
             }
-            let rulenum = rulenum.unwrap();
-            // Build the rule
-            let tokens = rulesplit.next().unwrap();
-            self.language.insert(rulenum, tokens.split(" | ").map(|x| {
-                x.split(' ').map(|u| {
-                    match u.parse::<usize>() {
-                        Ok(k)   => Token::Rule(k),
-                        Err(_)  =>
-                            Token::Literal(u.chars().nth(1).unwrap())
-                    }
-                }).collect::<Sequence>()
-            }).collect::<Rule>());
+            let (rulenum, rule) = parse_rule_line(rule).map_err(|kind|
+                Diagnostic { line: idx + 1, severity: Severity::Error, kind })?;
+            self.language.insert(rulenum, rule);
         }
         Ok(self.language.len())
     }
+    /// Build an analyzer from a whole set of rules, once
+    /// [validated](validate_rules)
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error found in the rules; warnings are let
+    /// through.
+    fn from_rules(rules: &str) -> Result<LexicalAnalyzer, Diagnostic> {
+        if let Some(error) = validate_rules(rules).into_iter()
+            .find(|d| d.severity == Severity::Error) {
+            return Err(error);
+        }
+        let mut lexer = LexicalAnalyzer::new();
+        lexer.inject_rules(rules)?;
+        Ok(lexer)
+    }
 
+    #[allow(dead_code)]
     fn resolve_literal<'a>(&self, data: &'a str, goals: &mut Vec<Token>)
         -> Result<&'a str,()>
     {
@@ -61,7 +81,7 @@ impl LexicalAnalyzer {
         match goals.pop() {
             None => {
                 // If there's nothing left to match it's cool
-                if data == "" {
+                if data.is_empty() {
                     Ok("")
                 } else {
                     // Otherwise it means we fucked up and need to backtrack
@@ -99,59 +119,419 @@ impl LexicalAnalyzer {
             }
         }
     }
+    /// Whether the whole of `data` matches rule 0, by backtracking. Only
+    /// works for rules that are not left recursive; kept as the reference
+    /// the other matchers are checked against.
+    #[allow(dead_code)]
     fn matches_literal(&self, data: &str) -> bool {
         self.resolve_literal(data, &mut vec![Token::Rule(0)]) == Ok("")
     }
+    /// Replace rules 8 and 11 by their looping versions, as part 2 asks
     fn fix_rules(&mut self) {
-        self.language.insert(8,
-                vec![
-                    vec![Token::Rule(42)],
-                    vec![Token::Rule(42), Token::Rule(8)]
-                ]
-        );
-        self.language.insert(11,
-                vec![
-                    vec![Token::Rule(42), Token::Rule(31)],
-                    vec![Token::Rule(42), Token::Rule(11), Token::Rule(31)]
-                ]
-        );
+        self.inject_rules("8: 42 | 42 8\n11: 42 31 | 42 11 31")
+            .expect("the rules of part 2 are well formed");
     }
 }
 
 /// # Errors
 ///
-/// Returns () because why not
-fn sol1(data: &str) -> Result<usize,()> {
-    let mut lexer = LexicalAnalyzer::new();
+/// Returns the first error found in the rules
+fn sol1(data: &str) -> Result<usize,Diagnostic> {
     let mut datasplit = data.split("\n\n");
     let rules = datasplit.next().unwrap();
-    let matches = datasplit.next().unwrap();
-    // Inject rules
-    let _ = lexer.inject_rules(rules);
-    Ok(matches.split('\n').filter(|x| lexer.matches_literal(x)).count())
+    let matches = datasplit.next().unwrap_or("");
+    let lexer = LexicalAnalyzer::from_rules(rules)?;
+    let dfa = lexer.compile_dfa(0);
+    Ok(matches.split('\n').filter(|x| dfa.matches(x)).count())
 }
 
 /// # Errors
 ///
-/// Returns () because why not
-fn sol2(data: &str) -> Result<usize,()> {
-    let mut lexer = LexicalAnalyzer::new();
+/// Returns the first error found in the rules
+fn sol2(data: &str) -> Result<usize,Diagnostic> {
     let mut datasplit = data.split("\n\n");
     let rules = datasplit.next().unwrap();
-    let matches = datasplit.next().unwrap();
-    // Inject rules
-    let _ = lexer.inject_rules(rules);
+    let matches = datasplit.next().unwrap_or("");
+    let mut lexer = LexicalAnalyzer::from_rules(rules)?;
     lexer.fix_rules();
-    Ok(matches.split('\n').filter(|x| lexer.matches_literal(x))
+    Ok(matches.split('\n').filter(|x| lexer.matches_earley(x))
        .count())
 }
 
+/// Run one of the grammar tools on the rules of `data` :
+/// `[--part2] <tool> [message...]`, messages defaulting to the ones of
+/// `data`
+///
+/// - `tree` prints how many parse trees every message has, and one of them
+/// - `regex` prints a regular expression for rule 0, unrolled enough for
+///   the messages
+/// - `explain` tells how far every message goes in matching rule 0, and
+///   what stops it
+/// - `check` lists the problems of the rules
+/// - `generate <length>` prints every message rule 0 matches up to that
+///   length
+/// - `sample <count>` prints random messages rule 0 matches
+/// - `dfa` prints the size of the minimized automaton for rule 0, and the
+///   messages it accepts
+fn run_tool(data: &str, args: &[String]) -> Result<(), String> {
+    let mut datasplit = data.split("\n\n");
+    let rules = datasplit.next().unwrap_or("");
+    let mut args = args.iter().map(String::as_str).peekable();
+    let part2 = args.peek() == Some(&"--part2");
+    if part2 {
+        args.next();
+    }
+    let tool = args.next().ok_or("no tool given")?;
+    if tool == "check" {
+        let diagnostics = validate_rules(rules);
+        for diagnostic in &diagnostics {
+            println!("{}", diagnostic);
+        }
+        return match diagnostics.iter().filter(|d| d.severity == Severity::Error).count() {
+            0 => Ok(()),
+            n => Err(format!("{} errors", n))
+        };
+    }
+    let mut lexer = LexicalAnalyzer::from_rules(rules).map_err(|d| d.to_string())?;
+    if part2 {
+        lexer.fix_rules();
+    }
+    let mut messages = args.collect::<Vec<&str>>();
+    if messages.is_empty() {
+        messages = datasplit.next().unwrap_or("").split('\n').collect();
+    }
+    match tool {
+        "explain" => {
+            for message in messages {
+                println!("{}\n", lexer.explain(message, 0));
+            }
+            Ok(())
+        },
+        "tree" => {
+            let grammar = lexer.to_cnf().map_err(|e| format!("{:?}", e))?;
+            for message in messages {
+                match grammar.parse(message, 0) {
+                    Some(tree) => println!("{}\t{}\t{}", message,
+                        grammar.count_parses(message, 0), tree),
+                    None => println!("{}\t0", message)
+                }
+            }
+            Ok(())
+        },
+        "generate" => {
+            let max_len = args_number(&messages)?;
+            for message in lexer.enumerate(0, max_len) {
+                println!("{}", message);
+            }
+            Ok(())
+        },
+        "sample" => {
+            let count = args_number(&messages)?;
+            let mut rng = XorShift::new(0x2020_1219);
+            for _ in 0..count {
+                println!("{}", lexer.sample(&mut rng, 0, 20).ok_or("rule 0 matches nothing")?);
+            }
+            Ok(())
+        },
+        "regex" => {
+            let depth = lexer.unrolling_depth(messages.iter().map(|m| m.len()).max().unwrap_or(0));
+            println!("{}", lexer.compile_regex(depth).ok_or("rule 0 matches nothing")?);
+            Ok(())
+        },
+        "dfa" => {
+            let depth = lexer.unrolling_depth(messages.iter().map(|m| m.len()).max().unwrap_or(0));
+            let dfa = lexer.compile_dfa(depth);
+            println!("{} states", dfa.states());
+            for message in messages.into_iter().filter(|m| dfa.matches(m)) {
+                println!("{}", message);
+            }
+            Ok(())
+        },
+        _ => Err(format!("unknown tool {:?}", tool))
+    }
+}
+
+/// The number given as only argument of a tool
+fn args_number(args: &[&str]) -> Result<usize, String> {
+    match args {
+        [n] => n.parse::<usize>().map_err(|e| format!("{}: {}", n, e)),
+        _ => Err("expected a single number".to_string())
+    }
+}
+
 fn main() {
     let tmp = read_data("input");
     if tmp.is_err() {
         panic!("Lango. Is. Dead.");
     }
     let data = tmp.unwrap();
+    let args = std::env::args().skip(1).collect::<Vec<String>>();
+    if !args.is_empty() {
+        if let Err(e) = run_tool(&data, &args) {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
     println!("{:?}", sol1(&data));
     println!("{:?}", sol2(&data));
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn analyzer(rules: &str) -> LexicalAnalyzer {
+        let mut lexer = LexicalAnalyzer::new();
+        lexer.inject_rules(rules).unwrap();
+        lexer
+    }
+
+    #[test]
+    fn example_part_1() {
+        let data = read_data("test_input").unwrap();
+        assert_eq!(sol1(&data), Ok(3));
+    }
+    #[test]
+    fn example_part_2() {
+        let data = read_data("test_input").unwrap();
+        assert_eq!(sol2(&data), Ok(12));
+    }
+    #[test]
+    fn earley_agrees_with_backtracking() {
+        let data = read_data("test_input").unwrap();
+        let mut datasplit = data.split("\n\n");
+        let lexer = analyzer(datasplit.next().unwrap());
+        for message in datasplit.next().unwrap().split('\n') {
+            assert_eq!(lexer.matches_earley(message), lexer.matches_literal(message));
+        }
+    }
+    #[test]
+    fn earley_left_recursion() {
+        // 0 is one or more "a" followed by a "b", written left recursively
+        let lexer = analyzer("0: 1 2\n1: 1 3 | 3\n2: \"b\"\n3: \"a\"");
+        assert!(lexer.matches_earley("ab"));
+        assert!(lexer.matches_earley("aaaab"));
+        assert!(!lexer.matches_earley("b"));
+        assert!(!lexer.matches_earley("aaba"));
+        // Balanced "a"s and "b"s, where 2 matches the empty message
+        let mut lexer = analyzer("0: 2\n1: \"a\"\n3: \"b\"");
+        lexer.language.insert(2, vec![vec![], vec![Token::Rule(1), Token::Rule(2), Token::Rule(3)]]);
+        assert!(lexer.matches_earley(""));
+        assert!(lexer.matches_earley("aaabbb"));
+        assert!(!lexer.matches_earley("aabbb"));
+    }
+    #[test]
+    fn cyk_agrees_with_earley() {
+        let data = read_data("test_input").unwrap();
+        let mut datasplit = data.split("\n\n");
+        let mut lexer = analyzer(datasplit.next().unwrap());
+        lexer.fix_rules();
+        let grammar = lexer.to_cnf().unwrap();
+        for message in datasplit.next().unwrap().split('\n') {
+            assert_eq!(grammar.parse(message, 0).is_some(), lexer.matches_earley(message));
+            assert_eq!(grammar.count_parses(message, 0) > 0, lexer.matches_earley(message));
+        }
+    }
+    #[test]
+    fn cyk_parse_tree() {
+        // Unit rule 4 and the literal inside a sequence are kept in the tree
+        let grammar = analyzer("0: 1 2 \"c\"\n1: \"a\"\n2: 4\n4: \"b\" | 1 1")
+            .to_cnf().unwrap();
+        let tree = grammar.parse("abc", 0).unwrap();
+        assert_eq!(tree.to_string(), "(0 (1 'a'@0) (2 (4 'b'@1)) 'c'@2)");
+        match tree {
+            ParseTree::Node { rule: 0, span: (0, 3), children } => {
+                assert_eq!(children.len(), 3);
+                assert!(matches!(children[1], ParseTree::Node { rule: 2, span: (1, 2), .. }));
+            },
+            _ => panic!("not a rule 0 node")
+        }
+        assert_eq!(grammar.parse("aaac", 0).unwrap().to_string(),
+            "(0 (1 'a'@0) (2 (4 (1 'a'@1) (1 'a'@2))) 'c'@3)");
+        assert_eq!(grammar.parse("abb", 0), None);
+    }
+    #[test]
+    fn cyk_ambiguity() {
+        let data = read_data("test_input").unwrap();
+        let mut lexer = analyzer(data.split("\n\n").next().unwrap());
+        assert_eq!(lexer.to_cnf().unwrap().count_parses("bbabbbbaabaabba", 0), 1);
+        // The number of 31 in rule 11 decides how 8 and 11 share the message
+        lexer.fix_rules();
+        let grammar = lexer.to_cnf().unwrap();
+        assert_eq!(grammar.count_parses("babbbbaabbbbbabbbbbbaabaaabaaa", 0), 1);
+        // Sums of "a" : every way to parenthesize is a parse tree
+        let grammar = analyzer("0: 1 | 0 0\n1: \"a\"").to_cnf().unwrap();
+        assert_eq!(grammar.count_parses("aaaa", 0), 5);
+    }
+    #[test]
+    fn dfa_agrees_with_earley() {
+        let data = read_data("test_input").unwrap();
+        let mut datasplit = data.split("\n\n");
+        let mut lexer = analyzer(datasplit.next().unwrap());
+        let messages = datasplit.next().unwrap().split('\n').collect::<Vec<&str>>();
+        let dfa = lexer.compile_dfa(0);
+        assert_eq!(messages.iter().filter(|m| dfa.matches(m)).count(), 3);
+        lexer.fix_rules();
+        let depth = lexer.unrolling_depth(messages.iter().map(|m| m.len()).max().unwrap());
+        let dfa = lexer.compile_dfa(depth);
+        for message in messages {
+            assert_eq!(dfa.matches(message), lexer.matches_earley(message));
+        }
+    }
+    #[test]
+    fn dfa_minimal() {
+        // Both sides of 0 match the same thing, so do 1 and 2
+        let lexer = analyzer("0: 1 3 | 2 3\n1: \"a\" \"b\" | \"b\"\n2: \"b\" | \"a\" 4\n3: \"c\"\n4: \"b\"");
+        let dfa = lexer.compile_dfa(0);
+        assert_eq!(dfa, analyzer("0: 1 2\n1: \"a\" \"b\" | \"b\"\n2: \"c\"").compile_dfa(0));
+        assert_eq!(dfa.states(), 4);
+        assert!(dfa.matches("abc") && dfa.matches("bc"));
+        assert!(!dfa.matches("ab") && !dfa.matches("abcc") && !dfa.matches(""));
+        assert_eq!(lexer.compile_regex(0).unwrap(), "^(?:(?:ab|b)c|(?:b|ab)c)$");
+    }
+    #[test]
+    fn dfa_unrolling() {
+        let lexer = analyzer("0: 1 | 1 0\n1: \"a\"");
+        assert_eq!(lexer.compile_regex(2).unwrap(), "^(?:a|a(?:a|aa))$");
+        let dfa = lexer.compile_dfa(2);
+        assert!(dfa.matches("aaa"));
+        assert!(!dfa.matches("aaaa"));
+        assert_eq!(lexer.compile_dfa(3).states(), 5);
+        assert_eq!(analyzer("0: 1 0\n1: \"a\"").compile_dfa(5), Dfa::nothing());
+        // One turn of 3 only takes the single "a" of 2, however long 1 is
+        let lexer = analyzer("0: 3\n3: 1 1 1 1 | 2 3\n1: \"b\" \"b\" \"b\" \"b\"\n2: \"a\"");
+        let message = format!("{}{}", "a".repeat(10), "b".repeat(16));
+        assert!(lexer.matches_earley(&message));
+        assert_eq!(lexer.unrolling_depth(message.len()), 26);
+        assert!(lexer.compile_dfa(lexer.unrolling_depth(message.len())).matches(&message));
+        assert!(!lexer.compile_dfa(9).matches(&message));
+    }
+    #[test]
+    fn validation() {
+        let data = read_data("test_input").unwrap();
+        assert_eq!(validate_rules(data.split("\n\n").next().unwrap()), vec![]);
+        let diagnostics = validate_rules("0: 1 2 | 3\n1: \"a\"\n2: 2 1\n\n1: \"ab\" | a\n4: 1\n5 \"b\"");
+        let found = diagnostics.iter()
+            .map(|d| (d.line, d.severity, d.kind.clone())).collect::<Vec<_>>();
+        assert_eq!(found, vec![
+            (1, Severity::Error, DiagnosticKind::UndefinedRule(3)),
+            (1, Severity::Error, DiagnosticKind::NonTerminating(0)),
+            (3, Severity::Error, DiagnosticKind::NonTerminating(2)),
+            (5, Severity::Error, DiagnosticKind::MalformedLiteral("\"ab\"".to_string())),
+            (6, Severity::Warning, DiagnosticKind::Unreachable(4)),
+            (7, Severity::Error, DiagnosticKind::MalformedLine)
+        ]);
+        assert_eq!(diagnostics[0].to_string(), "line 1: error: rule 3 is not defined");
+        let diagnostics = validate_rules("1: \"a\"\n1: \"b\"");
+        assert_eq!(diagnostics.iter().map(|d| d.to_string()).collect::<Vec<String>>(), vec![
+            "line 0: error: rule 0 is not defined",
+            "line 2: error: rule 1 is already defined on line 1"
+        ]);
+        assert_eq!(LexicalAnalyzer::new().inject_rules("0: 1\n\n1: \"\"").map_err(|d| d.to_string()),
+                   Err("line 3: error: \"\\\"\\\"\" is neither a rule number nor a quoted character".to_string()));
+        // Errors reach the solutions and the tools instead of being dropped
+        let data = "0: 1 2\n1: \"a\"\n\nab\naa";
+        assert_eq!(sol1(data).map_err(|d| d.to_string()), Err("line 1: error: rule 2 is not defined".to_string()));
+        assert_eq!(sol2(data).map_err(|d| d.line), Err(1));
+        let args = vec!["dfa".to_string()];
+        assert_eq!(run_tool(data, &args), Err("line 1: error: rule 2 is not defined".to_string()));
+        assert_eq!(run_tool(data, &["check".to_string()]), Err("2 errors".to_string()));
+        assert_eq!(sol1(&data.replace("1 2", "1 1")), Ok(1));
+    }
+    #[test]
+    fn enumeration() {
+        let lexer = analyzer("0: 1 2\n1: \"a\" | 1 \"a\"\n2: \"b\" | \"c\"");
+        let found = lexer.enumerate(0, 3).into_iter().collect::<Vec<String>>();
+        assert_eq!(found, vec!["aab", "aac", "ab", "ac"]);
+        // Every message of part 1 is three 5-character blocks, out of 16
+        // each; check them all
+        let data = read_data("test_input").unwrap();
+        let lexer = analyzer(data.split("\n\n").next().unwrap());
+        let accepted = lexer.enumerate(0, 15);
+        let dfa = lexer.compile_dfa(0);
+        assert_eq!(accepted.len(), 4096);
+        for bits in 0..1u32 << 15 {
+            let message = (0..15).map(|i| if bits >> i & 1 == 1 { 'b' } else { 'a' })
+                .collect::<String>();
+            assert_eq!(dfa.matches(&message), accepted.contains(&message));
+        }
+    }
+    #[test]
+    fn matchers_accept_generated() {
+        let data = read_data("test_input").unwrap();
+        let mut lexer = analyzer(data.split("\n\n").next().unwrap());
+        for message in lexer.enumerate(0, 15).into_iter().step_by(7) {
+            assert!(lexer.matches_literal(&message), "{}", message);
+            assert!(lexer.matches_earley(&message), "{}", message);
+        }
+        lexer.fix_rules();
+        let grammar = lexer.to_cnf().unwrap();
+        let mut rng = XorShift::new(19);
+        let samples = (0..100).filter_map(|_| lexer.sample(&mut rng, 0, 6)).collect::<Vec<String>>();
+        assert_eq!(samples.len(), 100);
+        let max_len = samples.iter().map(|s| s.len()).max().unwrap();
+        let dfa = lexer.compile_dfa(lexer.unrolling_depth(max_len));
+        for message in samples {
+            assert!(lexer.matches_earley(&message), "{}", message);
+            assert!(grammar.count_parses(&message, 0) > 0, "{}", message);
+            assert!(dfa.matches(&message), "{}", message);
+        }
+    }
+    #[test]
+    fn sampling_terminates() {
+        // Without the cap, 0 would grow forever two times out of three
+        let lexer = analyzer("0: 0 0 | 0 1 | 1\n1: \"a\"");
+        let mut rng = XorShift::new(2020);
+        for _ in 0..20 {
+            let message = lexer.sample(&mut rng, 0, 6).unwrap();
+            assert!(message.len() <= 1 << 6);
+            assert!(lexer.matches_earley(&message));
+        }
+        assert_eq!(analyzer("0: 0 1\n1: \"a\"").sample(&mut rng, 0, 6), None);
+    }
+    #[test]
+    fn explanations() {
+        let lexer = analyzer("0: 4 1 5\n1: 2 3 | 3 2\n2: 4 4 | 5 5\n3: 4 5 | 5 4\n4: \"a\"\n5: \"b\"");
+        let explanation = lexer.explain("aaabbb", 0);
+        assert!(!explanation.matched);
+        assert_eq!(explanation.furthest, 4);
+        assert_eq!(explanation.rule_stack, vec![0, 1, 3, 4]);
+        assert_eq!(explanation.to_string(),
+            "aaabbb\n    ^ no match past 4 characters\nexpected: 'a', rule 4\nrule stack: 0 > 1 > 3 > 4");
+        let explanation = lexer.explain("aaaab", 0);
+        assert_eq!(explanation.furthest, 5);
+        assert_eq!(explanation.expected_literals.iter().collect::<Vec<&char>>(), vec![&'b']);
+        assert!(explanation.to_string().contains("message ends too early"));
+        assert_eq!(lexer.explain("ababbb", 0).to_string(), "ababbb\nmatches rule 0");
+        assert_eq!(lexer.explain("ababbba", 0).expected_literals.len(), 0);
+    }
+    #[test]
+    fn explanations_with_loops() {
+        let data = read_data("test_input").unwrap();
+        let mut lexer = analyzer(data.split("\n\n").next().unwrap());
+        lexer.fix_rules();
+        for message in data.split("\n\n").nth(1).unwrap().split('\n') {
+            let explanation = lexer.explain(message, 0);
+            assert_eq!(explanation.matched, lexer.matches_earley(message));
+            assert!(explanation.matched || explanation.rule_stack.starts_with(&[0]));
+        }
+        // Loops are cut wherever the search enters them, so a stack found
+        // under a cut must not be reused from another item
+        let lexer = analyzer("0: 2 1 | 1\n1: 0 | \"a\" 0\n2: \"a\" 2 | 1 0");
+        let chart = lexer.earley_chart("aa", 0);
+        let mut memo = HashMap::new();
+        for item in chart[2].iter().filter(|i| matches!(lexer.next_token(i), Some(Token::Literal(_)))) {
+            let (shared, _) = lexer.rule_stack(&chart, 0, 2, *item, &mut HashSet::new(), &mut memo);
+            let (alone, _) = lexer.rule_stack(&chart, 0, 2, *item, &mut HashSet::new(), &mut HashMap::new());
+            assert_eq!(shared, alone);
+        }
+    }
+    #[test]
+    fn cnf_errors() {
+        assert_eq!(analyzer("0: 1\n1: 2\n2: 1 | \"a\"").to_cnf().err(), Some(CnfError::UnitCycle(1)));
+        let mut lexer = analyzer("0: 1 1\n1: \"a\"");
+        lexer.language.insert(1, vec![vec![]]);
+        assert_eq!(lexer.to_cnf().err(), Some(CnfError::EmptyAlternative(1)));
+    }
+}
@@ -0,0 +1,167 @@
+/// How bad a problem in the rules is
+#[derive(Clone,Copy,Eq,PartialEq,Ord,PartialOrd,Debug)]
+enum Severity {
+    /// Messages cannot be matched as the rules intend
+    Error,
+    /// Useless, but harmless
+    Warning
+}
+
+/// A problem found in the text of the rules
+#[derive(Clone,Eq,PartialEq,Ord,PartialOrd,Debug)]
+enum DiagnosticKind {
+    /// The line is not a rule number, a colon and alternatives
+    MalformedLine,
+    /// A token that is neither a rule number nor a quoted character
+    MalformedLiteral(String),
+    /// The line refers to a rule defined nowhere
+    UndefinedRule(usize),
+    /// The rule was already defined on `first_line`; the last definition
+    /// wins
+    DuplicateDefinition { rule: usize, first_line: usize },
+    /// There is no rule 0 to start matching from
+    MissingStart,
+    /// Rule 0 never leads to the rule
+    Unreachable(usize),
+    /// Every alternative of the rule loops forever or hits an undefined
+    /// rule, so it matches no message at all
+    NonTerminating(usize)
+}
+
+impl std::fmt::Display for DiagnosticKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DiagnosticKind::MalformedLine => write!(f, "expected `<number>: <alternatives>`"),
+            DiagnosticKind::MalformedLiteral(token) =>
+                write!(f, "{:?} is neither a rule number nor a quoted character", token),
+            DiagnosticKind::UndefinedRule(rule) => write!(f, "rule {} is not defined", rule),
+            DiagnosticKind::DuplicateDefinition { rule, first_line } =>
+                write!(f, "rule {} is already defined on line {}", rule, first_line),
+            DiagnosticKind::MissingStart => write!(f, "rule 0 is not defined"),
+            DiagnosticKind::Unreachable(rule) => write!(f, "rule {} is unreachable from rule 0", rule),
+            DiagnosticKind::NonTerminating(rule) => write!(f, "rule {} can never terminate", rule)
+        }
+    }
+}
+
+/// A problem, and the line (from 1) it is on. Problems about the rules as
+/// a whole are on line 0.
+#[derive(Clone,Eq,PartialEq,Ord,PartialOrd,Debug)]
+struct Diagnostic {
+    line: usize,
+    severity: Severity,
+    kind: DiagnosticKind
+}
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let severity = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning"
+        };
+        write!(f, "line {}: {}: {}", self.line, severity, self.kind)
+    }
+}
+
+/// Read a token of a rule : a rule number or a single quoted character
+fn parse_token(token: &str) -> Result<Token, DiagnosticKind> {
+    if let Ok(rule) = token.parse::<usize>() {
+        return Ok(Token::Rule(rule));
+    }
+    let mut chars = token.strip_prefix('"').and_then(|t| t.strip_suffix('"'))
+        .map(str::chars)
+        .ok_or_else(|| DiagnosticKind::MalformedLiteral(token.to_string()))?;
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Ok(Token::Literal(c)),
+        _ => Err(DiagnosticKind::MalformedLiteral(token.to_string()))
+    }
+}
+
+/// Read a line such as `8: 42 | 42 8`
+fn parse_rule_line(line: &str) -> Result<(usize, Rule), DiagnosticKind> {
+    let mut split = line.splitn(2, ':');
+    let rulenum = split.next().and_then(|n| n.trim().parse::<usize>().ok())
+        .ok_or(DiagnosticKind::MalformedLine)?;
+    let alternatives = split.next().ok_or(DiagnosticKind::MalformedLine)?;
+    let rule = alternatives.split('|').map(|alt| {
+        let seq = alt.split_whitespace().map(parse_token).collect::<Result<Sequence, _>>()?;
+        if seq.is_empty() { Err(DiagnosticKind::MalformedLine) } else { Ok(seq) }
+    }).collect::<Result<Rule, _>>()?;
+    Ok((rulenum, rule))
+}
+
+/// Check the text of a whole set of rules, blank lines being ignored
+///
+/// Diagnostics come sorted by line.
+fn validate_rules(rules: &str) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let error = |line, kind| Diagnostic { line, severity: Severity::Error, kind };
+    let mut language = Language::new();
+    let mut defined: HashMap<usize, usize> = HashMap::new();
+    for (idx, text) in rules.lines().enumerate() {
+        if text.trim().is_empty() {
+            continue;
+        }
+        match parse_rule_line(text) {
+            Err(kind) => diagnostics.push(error(idx + 1, kind)),
+            Ok((rulenum, rule)) => {
+                if let Some(first_line) = defined.insert(rulenum, idx + 1) {
+                    diagnostics.push(error(idx + 1,
+                        DiagnosticKind::DuplicateDefinition { rule: rulenum, first_line }));
+                }
+                language.insert(rulenum, rule);
+            }
+        }
+    }
+    let references = |rule: &Rule| {
+        let mut refs = rule.iter().flatten()
+            .filter_map(|tok| match tok { Token::Rule(r) => Some(*r), _ => None })
+            .collect::<Vec<usize>>();
+        refs.sort_unstable();
+        refs.dedup();
+        refs
+    };
+    for (rulenum, rule) in &language {
+        for r in references(rule).into_iter().filter(|r| !language.contains_key(r)) {
+            diagnostics.push(error(defined[rulenum], DiagnosticKind::UndefinedRule(r)));
+        }
+    }
+    if language.contains_key(&0) {
+        let mut reachable: HashSet<usize> = HashSet::new();
+        let mut todo = vec![0];
+        while let Some(rule) = todo.pop() {
+            if reachable.insert(rule) {
+                todo.extend(language.get(&rule).map(references).unwrap_or_default());
+            }
+        }
+        for rulenum in language.keys().filter(|r| !reachable.contains(r)) {
+            diagnostics.push(Diagnostic {
+                line: defined[rulenum],
+                severity: Severity::Warning,
+                kind: DiagnosticKind::Unreachable(*rulenum)
+            });
+        }
+    } else if !language.is_empty() {
+        diagnostics.push(error(0, DiagnosticKind::MissingStart));
+    }
+    // Rules matching at least one message, grown from the literals up
+    let mut productive: HashSet<usize> = HashSet::new();
+    loop {
+        let before = productive.len();
+        for (&rulenum, rule) in &language {
+            if rule.iter().any(|seq| seq.iter().all(|tok|
+                matches!(tok, Token::Literal(_)) ||
+                matches!(tok, Token::Rule(r) if productive.contains(r)))) {
+                productive.insert(rulenum);
+            }
+        }
+        if productive.len() == before {
+            break;
+        }
+    }
+    for rulenum in language.keys().filter(|r| !productive.contains(r)) {
+        diagnostics.push(error(defined[rulenum], DiagnosticKind::NonTerminating(*rulenum)));
+    }
+    diagnostics.sort();
+    diagnostics
+}
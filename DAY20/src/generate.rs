@@ -0,0 +1,143 @@
+include!("../../DAY19/src/xorshift.rs");
+
+/// A `width` by `height` bitmap of random pixels, row after row.
+fn noise(width: usize, height: usize, rng: &mut XorShift) -> Vec<Vec<bool>> {
+    (0..height).map(|_| (0..width).map(|_| rng.next_u64() >> 63 == 1).collect()).collect()
+}
+
+/// Error thrown when a bitmap cannot be read or cut into tiles.
+#[derive(Debug)]
+struct CutError;
+
+/// Read a bitmap of `'#'` and `'.'`, such as a printed [Picture](Picture).
+fn parse_bitmap(s: &str) -> Result<Vec<Vec<bool>>, CutError> {
+    let bitmap = s.trim().split('\n')
+        .map(|line| line.trim_end().chars().map(|c| match c {
+            '#' => Ok(true),
+            '.' => Ok(false),
+            _ => Err(CutError)
+        }).collect::<Result<Vec<bool>, CutError>>())
+        .collect::<Result<Vec<Vec<bool>>, CutError>>()?;
+    if bitmap.iter().any(|row| row.len() != bitmap[0].len()) {
+        return Err(CutError);
+    }
+    Ok(bitmap)
+}
+
+/// # A generated puzzle
+///
+/// The tiles in the day's format, along with the ground truth to check a
+/// solution against.
+struct Puzzle {
+    /// The tiles in the day's format, shuffled
+    text: String,
+    /// Row after row, the identifier of every tile cut from the bitmap and
+    /// the orientation it was turned to afterwards
+    layout: Vec<Vec<(usize, D4)>>
+}
+
+/// Error thrown when parsing a ground truth layout fails.
+#[derive(Debug)]
+struct LayoutParseError;
+
+/// Print the ground truth layout of a puzzle, one row of tiles per line,
+/// every tile given as `<identifier>/<orientation index>`.
+impl std::fmt::Display for Puzzle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for row in &self.layout {
+            writeln!(f, "{}", row.iter().map(|(id, d)| format!("{}/{}", id, d.index()))
+                .collect::<Vec<String>>().join(" "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Read back a layout printed by [Puzzle](Puzzle).
+fn parse_layout(s: &str) -> Result<Vec<Vec<(usize, D4)>>, LayoutParseError> {
+    let layout = s.trim().split('\n')
+        .map(|line| line.split_whitespace().map(|cell| {
+            let (id, o) = cell.split_once('/').ok_or(LayoutParseError)?;
+            let o = o.parse::<usize>().ok().filter(|&o| o < ORIENTATIONS).ok_or(LayoutParseError)?;
+            Ok((id.parse::<usize>().map_err(|_| LayoutParseError)?, D4::from_index(o)))
+        }).collect::<Result<Vec<(usize, D4)>, LayoutParseError>>())
+        .collect::<Result<Vec<Vec<(usize, D4)>>, LayoutParseError>>()?;
+    if layout[0].is_empty() || layout.iter().any(|row| row.len() != layout[0].len()) {
+        return Err(LayoutParseError);
+    }
+    Ok(layout)
+}
+
+/// Cut a bitmap into a puzzle of `size`×`size` tiles.
+///
+/// As in the day's input, neighbouring tiles share the pixels of their
+/// common border, so the bitmap must be `W×(size-1)+1` pixels wide and
+/// `H×(size-1)+1` pixels high to give `W×H` tiles. Every tile is then
+/// turned to one of the eight [orientations](D4) and given an identifier
+/// of four digits or more, all at random, and the tiles are shuffled.
+/// Small tiles show few different edges, so that the puzzle may well have
+/// other solutions than the bitmap it was cut from.
+///
+/// # Errors
+///
+/// Returns a [CutError](CutError) when the bitmap does not have the
+/// right dimensions, or when the size of the tiles is not one
+/// [Tile](Tile) accepts.
+fn cut(bitmap: &[Vec<bool>], size: usize, rng: &mut XorShift) -> Result<Puzzle, CutError> {
+    let step = size.checked_sub(1).filter(|&s| s > 0).ok_or(CutError)?;
+    let (pixels_x, pixels_y) = (bitmap.first().map_or(0, Vec::len), bitmap.len());
+    if pixels_x < size || pixels_y < size || (pixels_x - 1) % step != 0 || (pixels_y - 1) % step != 0 {
+        return Err(CutError);
+    }
+    let (width, height) = ((pixels_x - 1) / step, (pixels_y - 1) / step);
+    // Distinct identifiers, drawn from a range ten times as big as needed
+    let span = 9000.max(10 * width * height);
+    let mut ids: HashSet<usize> = HashSet::new();
+    while ids.len() < width * height {
+        ids.insert(1000 + rng.below(span));
+    }
+    let mut ids = ids.into_iter().collect::<Vec<usize>>();
+    ids.sort_unstable();
+    let mut layout = vec![Vec::with_capacity(width); height];
+    let mut tiles = Vec::with_capacity(width * height);
+    for ty in 0..height {
+        for tx in 0..width {
+            let rows = (0..size).map(|y| (0..size)
+                .map(|x| if bitmap[ty*step + y][tx*step + x] { '#' } else { '.' })
+                .collect::<String>())
+                .collect::<Vec<String>>();
+            let mut tile = rows.join("\n").parse::<Tile>().map_err(|_| CutError)?;
+            let id = ids.swap_remove(rng.below(ids.len()));
+            let d = D4::from_index(rng.below(ORIENTATIONS));
+            tile.orient(d);
+            layout[ty].push((id, d));
+            tiles.push(format!("Tile {}:\n{}", id, tile));
+        }
+    }
+    // Fisher-Yates shuffle
+    for i in (1..tiles.len()).rev() {
+        tiles.swap(i, rng.below(i + 1));
+    }
+    Ok(Puzzle { text: tiles.join("\n\n"), layout })
+}
+
+impl TileSet {
+    /// Check the built puzzle against the ground truth layout of a
+    /// generated one, returning how the whole solution is turned with
+    /// respect to it.
+    ///
+    /// A solution is as good as any of its rotations and flips: it
+    /// agrees with the layout if there is an orientation of the whole
+    /// grid such that every tile is where that orientation takes it, and
+    /// was laid turned just as much once its random turn is undone.
+    /// Requires that the puzzle be built.
+    fn check(&self, layout: &[Vec<(usize, D4)>]) -> Option<D4> {
+        let (width, height) = (layout.first().map_or(0, Vec::len), layout.len());
+        D4::all().find(|&g| g.dims(width, height) == (self.width, self.height)
+            && layout.iter().enumerate().all(|(row, tiles)| tiles.iter().enumerate()
+                .all(|(col, &(id, turn))| {
+                    let at = g.apply((row, col), width, height);
+                    self.final_puzzle.get(&at) == Some(&id)
+                        && self.tiles.get(&id).is_some_and(|t| turn.then(t.orientation) == g)
+                })))
+    }
+}
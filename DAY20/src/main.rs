@@ -41,14 +41,14 @@ lazy_static! {
 fn sol1(data: &str) -> Result<usize,()> {
     let mut pic = data.parse::<TileSet>().unwrap();
     //println!("{:?}", pic);
-    pic.build_all_possible_edges();
+    pic.build_edge_index();
     Ok(pic.get_corners().iter().product::<usize>())
 }
 
 /// # Solve Advent of Code day 20 part 2
 ///
 /// By far one of the hardest days so far, day 20 part 2 is solved with
-/// this function using a naive puzzle solving algorithm. The final
+/// this function using a backtracking puzzle solving algorithm. The final
 /// numeric result is returned in a [Result<usize,()>](Result)
 ///
 /// # Arguments
@@ -57,23 +57,144 @@ fn sol1(data: &str) -> Result<usize,()> {
 ///
 /// # Errors
 ///
-/// Returns Err(()) because I can't be bothered to create a real error.
-fn sol2(data: &str) -> Result<usize,()> {
+/// Returns a [SolveError](SolveError) when the puzzle cannot be built.
+fn sol2(data: &str) -> Result<usize,SolveError> {
     let mut pic = data.parse::<TileSet>().unwrap();
-    let mut s = pic.extract();
-    'o: for _ in 0..2 {
-        for _ in 0..4 {
-            if s.contains() { break 'o; }
-            else { s.rotate_right(); }
+    let mut s = pic.extract()?;
+    let monster = Pattern::sea_monster();
+    let matches = s.find(&monster, true);
+    s.highlight(&monster, &matches);
+    Ok(s.count())
+}
+
+/// Reassemble the tiles of a scan and print the picture, seams removed.
+///
+/// With `--render <file>`, the mosaic is written to that file instead, as
+/// a PBM if its name ends with `.pbm` and as a PGM otherwise. Rendering
+/// takes the following flags :
+///  - `--borders` keeps the borders of the tiles
+///  - `--grid` draws lines between tiles, and their identifiers
+///  - `--monsters` highlights the sea monsters
+///  - `--scale <n>` draws every cell as a square of `n` pixels
+///
+/// With `--check <layout>`, the solution is checked against the ground
+/// truth layout of a [generated](generate) puzzle.
+///
+/// # Errors
+///
+/// Returns a message when the scan cannot be read or assembled, or when
+/// the arguments make no sense.
+fn reassemble(path: &str, args: &[String]) -> Result<(), String> {
+    let data = read_data(path).map_err(|e| format!("{}: {}", path, e))?;
+    let mut set = data.parse::<TileSet>().map_err(|_| format!("{}: bad tiles", path))?;
+    let mut options = RenderOptions::default();
+    let mut output: Option<&str> = None;
+    let mut layout: Option<&str> = None;
+    let mut args = args.iter().map(String::as_str);
+    while let Some(arg) = args.next() {
+        match arg {
+            "--render" => output = Some(args.next().ok_or("--render needs a file")?),
+            "--check" => layout = Some(args.next().ok_or("--check needs a file")?),
+            "--borders" => options.borders = true,
+            "--grid" => options.gridlines = true,
+            "--monsters" => options.monsters = true,
+            "--scale" => options.scale = args.next().and_then(|n| n.parse().ok())
+                .filter(|&n| n > 0).ok_or("--scale needs a positive number")?,
+            _ => return Err(format!("unknown argument {}", arg))
         }
-        s.flip_lr();
     }
-    s.find_and_replace();
-    Ok(s.count())
+    if let Some(layout) = layout {
+        let truth = read_data(layout).map_err(|e| format!("{}: {}", layout, e))?;
+        let truth = parse_layout(&truth).map_err(|_| format!("{}: bad layout", layout))?;
+        set.build().map_err(|e| e.to_string())?;
+        match set.check(&truth) {
+            Some(d) => eprintln!("{}: solution agrees, seen through {:?}", layout, d),
+            None => return Err(format!("{}: solution disagrees", layout))
+        }
+    }
+    if let Some(output) = output {
+        let raster = set.render(options).map_err(|e| e.to_string())?;
+        let bytes = if output.ends_with(".pbm") { raster.pbm() } else { raster.pgm() };
+        std::fs::write(output, bytes).map_err(|e| format!("{}: {}", output, e))?;
+        eprintln!("{}×{} tiles, {}×{} image", set.width, set.height, raster.width, raster.height);
+        return Ok(());
+    }
+    let picture = set.extract().map_err(|e| e.to_string())?;
+    let (width, height) = picture.size();
+    eprintln!("{}×{} tiles, {}×{} picture", set.width, set.height, width, height);
+    print!("{:?}", picture);
+    Ok(())
+}
+
+/// Generate a puzzle and print its tiles, in the day's format.
+///
+/// The puzzle is cut from random noise, or from the bitmap in the file
+/// given with `--from`, which takes the following flags :
+///  - `--tiles <W>x<H>` sets the number of tiles of random noise (4x4)
+///  - `--size <n>` sets the size of the tiles (10)
+///  - `--seed <n>` seeds the random generator
+///  - `--layout <file>` writes the ground truth layout to that file
+///
+/// # Errors
+///
+/// Returns a message when the bitmap cannot be read or cut, or when the
+/// arguments make no sense.
+fn generate(args: &[String]) -> Result<(), String> {
+    let (mut width, mut height, mut size, mut seed) = (4, 4, 10, 0x2020_1220);
+    let (mut from, mut layout): (Option<&str>, Option<&str>) = (None, None);
+    let mut args = args.iter().map(String::as_str);
+    let number = |arg: Option<&str>| arg.and_then(|n| n.parse::<usize>().ok())
+        .filter(|&n| n > 0);
+    while let Some(arg) = args.next() {
+        match arg {
+            "--tiles" => {
+                let dims = args.next().and_then(|t| t.split_once('x'))
+                    .and_then(|(w, h)| Some((number(Some(w))?, number(Some(h))?)))
+                    .ok_or("--tiles needs <W>x<H>")?;
+                width = dims.0;
+                height = dims.1;
+            },
+            "--size" => size = number(args.next()).ok_or("--size needs a positive number")?,
+            "--seed" => seed = args.next().and_then(|n| n.parse().ok()).ok_or("--seed needs a number")?,
+            "--from" => from = Some(args.next().ok_or("--from needs a file")?),
+            "--layout" => layout = Some(args.next().ok_or("--layout needs a file")?),
+            _ => return Err(format!("unknown argument {}", arg))
+        }
+    }
+    let mut rng = XorShift::new(seed);
+    let bitmap = match from {
+        Some(path) => {
+            let data = read_data(path).map_err(|e| format!("{}: {}", path, e))?;
+            parse_bitmap(&data).map_err(|_| format!("{}: bad bitmap", path))?
+        },
+        None if size > 1 => noise(width*(size-1) + 1, height*(size-1) + 1, &mut rng),
+        None => return Err("tiles need a size of 3 or more".to_string())
+    };
+    let puzzle = cut(&bitmap, size, &mut rng)
+        .map_err(|_| format!("cannot cut a {}×{} bitmap into tiles of {}",
+                             bitmap.first().map_or(0, Vec::len), bitmap.len(), size))?;
+    if let Some(path) = layout {
+        std::fs::write(path, puzzle.to_string()).map_err(|e| format!("{}: {}", path, e))?;
+    }
+    println!("{}", puzzle.text);
+    Ok(())
 }
 
 #[doc(hidden)]
 fn main() {
+    let args = std::env::args().skip(1).collect::<Vec<String>>();
+    if let Some(path) = args.first() {
+        let res = if path == "--generate" {
+            generate(&args[1..])
+        } else {
+            reassemble(path, &args[1..])
+        };
+        if let Err(e) = res {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
     let data = read_data("input");
     if data.is_err() {
         panic!("LOOK AT THIS PHOTOGRAPH!");
@@ -95,11 +216,271 @@ mod test {
         assert_eq!(tile.edge_left(), 962);
         assert_eq!(tile.edge_down(), 397);
         assert_eq!(tile.edge_right(), 576);
-        println!("{}", tile.to_string());
+        // Rows are stored bit-packed, leftmost pixel first
+        assert_eq!(tile.rows[0], 85);
+        assert_eq!(tile.rows[9], 0b1011000110);
+        println!("{}", tile);
         tile.rotate_left();
-        println!("{}", tile.to_string());
+        println!("{}", tile);
         tile.flip_ud();
-        println!("{}", tile.to_string());
+        println!("{}", tile);
+        // Edges would not fit in a usize
+        let wide = vec![".".repeat(MAX_TILE_SIZE + 1); MAX_TILE_SIZE + 1].join("\n");
+        assert!(matches!(wide.parse::<Tile>(), Err(TileParseError::TooLarge)));
+        assert!(matches!("#.\n.#".parse::<Tile>(), Err(TileParseError::Malformed)));
+    }
+    #[test]
+    fn oriented_edges() {
+        let data = read_data("test_input").unwrap();
+        let set = data.parse::<TileSet>().unwrap();
+        let tile = &set.tiles[&2311];
+        for d in D4::all() {
+            let mut oriented = tile.to_string().parse::<Tile>().unwrap();
+            oriented.orient(d);
+            assert_eq!(oriented.edges(), tile.oriented_edges(d));
+            // The view shows the edges it is said to
+            let reparsed = oriented.to_string().parse::<Tile>().unwrap();
+            assert_eq!(reparsed.edges(), oriented.edges());
+        }
+        // Orientations are all different
+        let mut all = D4::all().map(|d| tile.oriented_edges(d)).collect::<Vec<_>>();
+        all.dedup();
+        assert_eq!(all.len(), ORIENTATIONS);
+    }
+    #[test]
+    fn orientations() {
+        let (width, height) = (3, 5);
+        let cells = (0..width*height).map(|i| (i / width, i % width)).collect::<Vec<_>>();
+        for a in D4::all() {
+            assert_eq!(D4::from_index(a.index()), a);
+            assert_eq!(a.then(a.inverse()), D4::IDENTITY);
+            assert_eq!(a.inverse().then(a), D4::IDENTITY);
+            let (w, h) = a.dims(width, height);
+            for b in D4::all() {
+                // Composing is the same as applying one after the other
+                let ab = a.then(b);
+                for &cell in &cells {
+                    assert_eq!(ab.apply(cell, width, height),
+                               b.apply(a.apply(cell, width, height), w, h));
+                }
+            }
+            for &cell in &cells {
+                assert_eq!(a.source(a.apply(cell, width, height), width, height), cell);
+            }
+        }
+        assert_eq!(D4::FLIP_LR.then(D4::rotation(2)), D4::FLIP_UD);
+        assert_eq!(D4::rotation(1).then(D4::FLIP_LR), D4::FLIP_LR.then(D4::rotation(3)));
+        // Four quarter turns and two flips get back where they started
+        let mut tile = "#..\n...\n..#".parse::<Tile>().unwrap();
+        let before = tile.to_string();
+        for _ in 0..4 { tile.rotate_right(); }
+        tile.flip_lr();
+        tile.flip_ud();
+        assert_eq!(tile.orientation, D4::rotation(2));
+        assert_eq!(tile.to_string(), before);
+        tile.rotate_left();
+        assert_eq!(tile.to_string(), "..#\n...\n#..");
+    }
+    #[test]
+    fn edge_index() {
+        let data = read_data("test_input").unwrap();
+        let mut set = data.parse::<TileSet>().unwrap();
+        set.build_edge_index();
+        assert_eq!(set.get_corners(), [1951, 3079, 2971, 1171].iter().copied().collect());
+        let unmatched = set.unmatched_sides();
+        assert_eq!(unmatched.values().filter(|&&n| n == 1).count(), 4);
+        assert_eq!(unmatched[&1427], 0);
+        // Every side shows up once, under the same pattern as its flip
+        assert_eq!(set.edge_index.values().map(Vec::len).sum::<usize>(), 4*9);
+        assert!(set.edge_index.values().all(|sides| sides.len() <= 2));
+        let tile = &set.tiles[&2311];
+        assert!(!set.is_unique(tile.edge_up(), 10));
+        assert!(!set.is_unique(flip_side(tile.edge_up(), 10), 10));
+        assert!(set.is_unique(tile.edge_down(), 10));
+    }
+    #[test]
+    fn shared_edges() {
+        // Blank tiles all fit each other, there are no unique edges
+        let tile = [".........."; 10].join("\n");
+        let data = (0..9).map(|i| format!("Tile {}:\n{}", 1000 + i, tile))
+            .collect::<Vec<String>>().join("\n\n");
+        let mut set = data.parse::<TileSet>().unwrap();
+        let picture = set.extract().unwrap();
+        assert_eq!(picture.size(), (24, 24));
+        assert_eq!(picture.count(), 0);
+        assert_eq!(set.final_puzzle.values().collect::<HashSet<_>>().len(), 9);
+    }
+    #[test]
+    fn no_solution() {
+        let data = read_data("test_input").unwrap();
+        let mut set = data.parse::<TileSet>().unwrap();
+        set.tiles.remove(&1951);
+        assert_eq!(set.build(), Err(SolveError::NoSolution));
+        assert_eq!("".parse::<TileSet>().map(|mut s| s.build()).ok(), None);
+        // Break the edge between 1951 and 2311 by changing one pixel
+        let broken = data.replace("Tile 1951:\n#.##...##.\n#.####...#\n.....#..##\n#...######",
+                                  "Tile 1951:\n#.##...##.\n#.####...#\n.....#..##\n#...#####.");
+        assert_ne!(broken, data);
+        let mut set = broken.parse::<TileSet>().unwrap();
+        assert_eq!(set.build(), Err(SolveError::NoSolution));
+        assert_eq!(sol2(&broken), Err(SolveError::NoSolution));
+    }
+    /// Cut random noise drawn from `seed` into a puzzle of `width` by
+    /// `height` tiles of `size`×`size`, and solve it back to its ground truth
+    fn generated(width: usize, height: usize, size: usize, seed: u64)
+                 -> (Vec<Vec<bool>>, Puzzle, TileSet) {
+        let mut rng = XorShift::new(seed);
+        let bitmap = noise(width*(size-1) + 1, height*(size-1) + 1, &mut rng);
+        let puzzle = cut(&bitmap, size, &mut rng).unwrap();
+        let mut set = puzzle.text.parse::<TileSet>().unwrap();
+        assert_eq!(set.tiles.len(), width*height);
+        set.build().unwrap();
+        assert!(set.check(&puzzle.layout).is_some());
+        (bitmap, puzzle, set)
+    }
+    #[test]
+    fn rectangular_mosaic() {
+        // 3×2 tiles of 7×7, the seams being shared by neighbours
+        let (bitmap, puzzle, mut set) = generated(3, 2, 7, 20);
+        assert_eq!(set.layouts()[0], (2, 3));
+        assert_eq!((set.width, set.height), (2, 3));
+        let mut picture = set.extract().unwrap();
+        // The picture is the bitmap without seams, up to a rotation or flip
+        let expected = bitmap.iter().enumerate().filter(|(y, _)| y % 6 != 0)
+            .map(|(_, row)| row.iter().enumerate().filter(|(x, _)| x % 6 != 0)
+                .map(|(_, &b)| if b { '#' } else { '.' }).collect::<String>() + "\n")
+            .collect::<String>();
+        assert!(D4::all().any(|d| {
+            picture.orientation = d;
+            format!("{:?}", picture) == expected
+        }));
+        // A single row of tiles has two ends instead of four corners
+        let (_, _, row) = generated(5, 1, 7, 3);
+        assert_eq!(row.layouts()[0], (1, 5));
+        assert_eq!((row.width, row.height), (1, 5));
+        // Tiles of different sizes do not make a set
+        let mut rng = XorShift::new(1);
+        let other = cut(&noise(9, 9, &mut rng), 9, &mut rng).unwrap();
+        let mixed = format!("{}\n\n{}", puzzle.text, other.text);
+        assert!(mixed.parse::<TileSet>().is_err());
+    }
+    #[test]
+    fn long_puzzle() {
+        // 5×40 tiles share too many edges for the unique ones to tell
+        // the layout apart from 10×20 or 8×25, it is found all the same
+        let (_, _, set) = generated(5, 40, 10, 2);
+        assert_eq!((set.width, set.height), (5, 40));
+        assert!(set.layouts().contains(&(5, 40)));
+        assert_eq!(Solver::new(&set, (5, 40), SEARCH_STEPS).run(), Ok(true));
+    }
+    #[test]
+    fn large_puzzle() {
+        // 480 tiles of 10×10 show most of the 528 edge patterns, many of
+        // them on several tiles
+        let (_, _, set) = generated(24, 20, 10, 7);
+        assert!(set.edge_index.values().any(|sides| sides.len() > 2));
+        assert_eq!((set.width, set.height), (20, 24));
+    }
+    #[test]
+    fn patterns() {
+        let monster = Pattern::sea_monster();
+        assert_eq!((monster.width, monster.height, monster.cells.len()), (20, 3, 15));
+        assert_eq!(monster.symmetries().len(), 8);
+        assert!("...\n.-.".parse::<Pattern>().is_err());
+        // Blank rows and columns around the shape do not count
+        let bar = "\n  ##\n".parse::<Pattern>().unwrap();
+        assert_eq!(bar.cells, vec![(0, 0), (0, 1)]);
+        assert_eq!(bar.symmetries().iter().map(|(d, _)| *d).collect::<Vec<_>>(),
+                   vec![D4::IDENTITY, D4::rotation(1)]);
+        let picture = Picture {
+            data: "###.".chars().enumerate().map(|(x, c)| ((0, x), c)).collect(),
+            width: 4, height: 1, orientation: D4::IDENTITY
+        };
+        assert_eq!(picture.find(&bar, true), vec![
+            Match { orientation: D4::IDENTITY, row: 0, col: 0 },
+            Match { orientation: D4::IDENTITY, row: 0, col: 1 }
+        ]);
+        assert_eq!(picture.find(&bar, false).len(), 1);
+    }
+    #[test]
+    fn sea_monsters() {
+        let data = read_data("test_input").unwrap();
+        let picture = data.parse::<TileSet>().unwrap().extract().unwrap();
+        let matches = picture.find(&Pattern::sea_monster(), false);
+        assert_eq!(matches.len(), 2);
+        // Both monsters are seen the same way
+        assert_eq!(matches[0].orientation, matches[1].orientation);
+        // Oriented the other way, the picture shows them in another orientation
+        let mut flipped = picture;
+        flipped.flip_lr();
+        let again = flipped.find(&Pattern::sea_monster(), false);
+        assert_eq!(again.len(), 2);
+        assert_ne!(again[0].orientation, matches[0].orientation);
+    }
+    #[test]
+    fn render() {
+        let data = read_data("test_input").unwrap();
+        let mut set = data.parse::<TileSet>().unwrap();
+        // Without options, the image is the picture
+        let picture = set.extract().unwrap();
+        let raster = set.render(RenderOptions::default()).unwrap();
+        assert_eq!((raster.width, raster.height), (24, 24));
+        assert!((0..24*24).all(|i| (raster.get(i / 24, i % 24) == INK)
+                                   == (picture.get(i / 24, i % 24) == '#')));
+        let monsters = set.render(RenderOptions { monsters: true, ..Default::default() }).unwrap();
+        assert_eq!(monsters.pixels.iter().filter(|&&p| p == MONSTER).count(), 2*15);
+        assert_eq!(monsters.pixels.iter().filter(|&&p| p != PAPER).count(), picture.count());
+        // 3×3 tiles of 10×10 cells of 2×2 pixels, and two gridlines each way
+        let options = RenderOptions { borders: true, gridlines: true, monsters: true, scale: 2 };
+        let raster = set.render(options).unwrap();
+        assert_eq!((raster.width, raster.height), (62, 62));
+        assert!((0..62).all(|i| raster.get(i, 20) == GRIDLINE && raster.get(41, i) == GRIDLINE));
+        // The identifier of the top left tile is written in its corner
+        let id = set.final_puzzle[&(0, 0)];
+        let mut labelled = Raster::new(17, 7, GRIDLINE);
+        assert!(labelled.label((0, 0), 20, 20, id));
+        assert!((0..7).all(|y| (0..17).all(|x| raster.get(y, x) == labelled.get(y, x))));
+        assert!(!labelled.label((0, 0), 16, 20, id));
+        let pbm = raster.pbm();
+        assert!(pbm.starts_with(b"P4\n62 62\n"));
+        assert_eq!(pbm.len(), "P4\n62 62\n".len() + 62*8);
+        let pgm = raster.pgm();
+        assert_eq!(pgm.len(), "P5\n62 62\n255\n".len() + 62*62);
+    }
+    #[test]
+    fn generated_puzzles() {
+        for &(width, height, size, seed) in &[(4, 4, 10, 47), (5, 3, 12, 48), (1, 6, 16, 49)] {
+            let (_, puzzle, set) = generated(width, height, size, seed);
+            // The layout survives being written down
+            let layout = parse_layout(&puzzle.to_string()).unwrap();
+            assert_eq!(layout, puzzle.layout);
+            // Swapping two tiles of the truth makes it wrong
+            let mut wrong = layout;
+            let (a, b) = (wrong[0][0], wrong[height-1][width-1]);
+            wrong[0][0] = b;
+            wrong[height-1][width-1] = a;
+            assert_eq!(set.check(&wrong), None);
+        }
+        let mut rng = XorShift::new(47);
+        // A cut picture comes back without the seams
+        let data = read_data("test_input").unwrap();
+        let picture = format!("{:?}", data.parse::<TileSet>().unwrap().extract().unwrap());
+        let bitmap = parse_bitmap(&picture).unwrap();
+        let crop = bitmap[..19].iter().map(|row| row[..19].to_vec()).collect::<Vec<_>>();
+        let expected = crop.iter().enumerate().filter(|(y, _)| y % 9 != 0)
+            .map(|(_, row)| row.iter().enumerate().filter(|(x, _)| x % 9 != 0)
+                .map(|(_, &b)| if b { '#' } else { '.' }).collect::<String>() + "\n")
+            .collect::<String>();
+        let puzzle = cut(&crop, 10, &mut rng).unwrap();
+        let mut again = puzzle.text.parse::<TileSet>().unwrap().extract().unwrap();
+        assert!(D4::all().any(|d| {
+            again.orientation = d;
+            format!("{:?}", again) == expected
+        }));
+        assert!(cut(&bitmap, 6, &mut rng).is_err());
+        assert!(cut(&noise(5, 5, &mut rng), 1, &mut rng).is_err());
+        assert!(parse_bitmap("#.\n#").is_err());
+        assert!(parse_layout("1/0 2/9").is_err());
     }
     #[test]
     fn sol1_example() {
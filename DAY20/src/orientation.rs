@@ -0,0 +1,85 @@
+/// Number of ways to lay a tile : four rotations, flipped or not.
+const ORIENTATIONS: usize = 8;
+
+/// # Orientation of a bitmap
+///
+/// One of the eight symmetries of the square, which make the dihedral
+/// group D4. A bitmap in orientation `D4 { flip, rotations }` is the
+/// original one flipped left to right if `flip` is set, *then* rotated
+/// 90° clockwise `rotations` times.
+///
+/// ## Referential
+///
+/// Cells are `(row, col)` in a raster referential, on bitmaps of `width`
+/// columns and `height` rows; rotations swap the two.
+#[derive(Debug,Clone,Copy,PartialEq,Eq,Hash)]
+struct D4 {
+    flip: bool,
+    /// Quarter turns to the right, from 0 to 3
+    rotations: u8
+}
+
+impl D4 {
+    /// Leave the bitmap as it is.
+    const IDENTITY: D4 = D4 { flip: false, rotations: 0 };
+    /// Flip the bitmap left to right.
+    const FLIP_LR: D4 = D4 { flip: true, rotations: 0 };
+    /// Flip the bitmap upside down.
+    const FLIP_UD: D4 = D4 { flip: true, rotations: 2 };
+
+    /// Rotate the bitmap `n` quarter turns to the right.
+    fn rotation(n: u8) -> Self {
+        D4 { flip: false, rotations: n % 4 }
+    }
+    /// The eight orientations, in [index](D4::index) order.
+    fn all() -> impl Iterator<Item=D4> {
+        (0..ORIENTATIONS).map(D4::from_index)
+    }
+    /// A number from 0 to 7 : 4 for a flip, plus the rotations.
+    fn index(self) -> usize {
+        usize::from(self.flip) * 4 + usize::from(self.rotations)
+    }
+    /// The orientation numbered `i` by [D4::index](D4::index).
+    fn from_index(i: usize) -> Self {
+        D4 { flip: i % ORIENTATIONS >= 4, rotations: (i % 4) as u8 }
+    }
+    /// The orientation of applying `self`, then `next`.
+    ///
+    /// A flip reverses the direction of the rotations before it, which
+    /// is all there is to it.
+    fn then(self, next: D4) -> Self {
+        let rotations = if next.flip {
+            next.rotations + 4 - self.rotations
+        } else {
+            next.rotations + self.rotations
+        };
+        D4 { flip: self.flip != next.flip, rotations: rotations % 4 }
+    }
+    /// The orientation undoing `self`.
+    fn inverse(self) -> Self {
+        if self.flip { self } else { D4::rotation(4 - self.rotations) }
+    }
+    /// The (width, height) of a bitmap once oriented.
+    fn dims(self, width: usize, height: usize) -> (usize, usize) {
+        if self.rotations.is_multiple_of(2) { (width, height) } else { (height, width) }
+    }
+    /// Where the cell `(row, col)` of a bitmap of `width` by `height`
+    /// ends up once the bitmap is oriented.
+    fn apply(self, (row, col): (usize, usize), width: usize, height: usize) -> (usize, usize) {
+        let (mut row, mut col) = if self.flip { (row, width-1-col) } else { (row, col) };
+        let (mut width, mut height) = (width, height);
+        for _ in 0..self.rotations {
+            let turned = (col, height-1-row);
+            row = turned.0;
+            col = turned.1;
+            std::mem::swap(&mut width, &mut height);
+        }
+        (row, col)
+    }
+    /// The cell of the original bitmap of `width` by `height` seen at
+    /// `(row, col)` once it is oriented.
+    fn source(self, cell: (usize, usize), width: usize, height: usize) -> (usize, usize) {
+        let (w, h) = self.dims(width, height);
+        self.inverse().apply(cell, w, h)
+    }
+}
@@ -0,0 +1,123 @@
+/// The sea monster of the day, as an ASCII template.
+const SEA_MONSTER: &str = "                  # \n#    ##    ##    ###\n #  #  #  #  #  #   ";
+
+/// # Pattern structure
+///
+/// A shape to look for in a [Picture](Picture), read from an ASCII
+/// template where every `'#'` is a cell that must be a `'#'` in the
+/// picture too. Any other character is a cell that can be anything.
+///
+/// Cells are stored as `(row, col)` offsets from the top left corner of
+/// the template, in a raster referential.
+#[derive(Debug,Clone,PartialEq,Eq)]
+struct Pattern {
+    /// Offsets of the cells that must be `'#'`, sorted
+    cells: Vec<(usize,usize)>,
+    /// Number of columns spanned by the pattern
+    width: usize,
+    /// Number of rows spanned by the pattern
+    height: usize
+}
+
+/// Error thrown when a template has no `'#'` at all.
+#[derive(Debug)]
+struct PatternParseError;
+
+/// Parse an ASCII template into a `Pattern`.
+///
+/// Rows and columns without any `'#'` around the shape are dropped.
+impl std::str::FromStr for Pattern {
+    type Err = PatternParseError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let cells = s.split('\n').enumerate()
+            .flat_map(|(row, line)| line.chars().enumerate()
+                .filter(|&(_, c)| c == '#')
+                .map(move |(col, _)| (row, col)))
+            .collect::<Vec<(usize,usize)>>();
+        let top = cells.iter().map(|c| c.0).min().ok_or(PatternParseError)?;
+        let left = cells.iter().map(|c| c.1).min().ok_or(PatternParseError)?;
+        Ok(Pattern::from_cells(cells.iter().map(|&(r, c)| (r - top, c - left))))
+    }
+}
+
+/// A place where a [Pattern](Pattern) was found in a picture : the top
+/// left corner of the pattern, laid in `orientation`.
+#[derive(Debug,Clone,Copy,PartialEq,Eq,Hash)]
+struct Match {
+    orientation: D4,
+    row: usize,
+    col: usize
+}
+
+impl Pattern {
+    /// Build a pattern from cells whose smallest row and column are 0.
+    fn from_cells(cells: impl Iterator<Item=(usize,usize)>) -> Self {
+        let mut cells = cells.collect::<Vec<(usize,usize)>>();
+        cells.sort_unstable();
+        Pattern {
+            height: cells.iter().map(|c| c.0 + 1).max().unwrap_or(0),
+            width: cells.iter().map(|c| c.1 + 1).max().unwrap_or(0),
+            cells
+        }
+    }
+    /// The sea monster of the day.
+    fn sea_monster() -> Self {
+        SEA_MONSTER.parse().unwrap()
+    }
+    /// The pattern laid in orientation `d`, the same way tiles are.
+    fn oriented(&self, d: D4) -> Self {
+        Pattern::from_cells(self.cells.iter().map(|&cell| d.apply(cell, self.width, self.height)))
+    }
+    /// The distinct shapes of the pattern across all orientations, along
+    /// with the first orientation giving them.
+    fn symmetries(&self) -> Vec<(D4, Pattern)> {
+        let mut res: Vec<(D4, Pattern)> = Vec::new();
+        for d in D4::all() {
+            let shape = self.oriented(d);
+            if res.iter().all(|(_, s)| *s != shape) {
+                res.push((d, shape));
+            }
+        }
+        res
+    }
+}
+
+impl Picture {
+    /// Find a pattern in the picture, in all of its orientations.
+    ///
+    /// Matches come in raster order for every orientation in turn. Unless
+    /// `overlapping` is set, a match sharing a cell with one found before
+    /// is left out.
+    fn find(&self, pattern: &Pattern, overlapping: bool) -> Vec<Match> {
+        let mut res = Vec::new();
+        let mut taken: HashSet<(usize,usize)> = HashSet::new();
+        let (width, height) = self.size();
+        for (orientation, shape) in pattern.symmetries() {
+            if shape.height > height || shape.width > width {
+                continue;
+            }
+            for row in 0..=height-shape.height {
+                for col in 0..=width-shape.width {
+                    let cells = shape.cells.iter().map(|&(r, c)| (row+r, col+c));
+                    if !cells.clone().all(|(r, c)| self.get(r, c) == '#') {
+                        continue;
+                    }
+                    if !overlapping {
+                        if cells.clone().any(|p| taken.contains(&p)) { continue; }
+                        taken.extend(cells);
+                    }
+                    res.push(Match { orientation, row, col });
+                }
+            }
+        }
+        res
+    }
+    /// Replace the `'#'` of every match of a pattern with `'O'`.
+    fn highlight(&mut self, pattern: &Pattern, matches: &[Match]) {
+        for m in matches {
+            for (r, c) in pattern.oriented(m.orientation).cells {
+                self.set(m.row+r, m.col+c, 'O');
+            }
+        }
+    }
+}
@@ -1,6 +1,6 @@
 /// # Picture Structure
 ///
-/// A `Picture` is a structure meant to hold a square
+/// A `Picture` is a structure meant to hold a rectangular
 /// bitmap of chars, with methods to rotate and flip it.
 /// It must be extracted from a [TileSet](TileSet) using
 /// [TileSet::extract](TileSet::extract).
@@ -10,11 +10,18 @@
 /// Since the indices of the hashmap that contains our characters
 /// are `(usize, usize)`, the top left corner is considered to be
 /// at coordinates `(0, 0)`, in a raster referential.
+///
+/// Rotating or flipping the picture does not move the characters
+/// around: they are seen through its [orientation](D4) instead.
 struct Picture {
-    /// Raw hashmap containing the characters
+    /// Raw hashmap containing the characters, as extracted
     data: HashMap<(usize,usize),char>,
-    /// Length of the sides of the square picture
-    sidelen: usize
+    /// Number of characters in a row of the picture, as extracted
+    width: usize,
+    /// Number of rows of the picture, as extracted
+    height: usize,
+    /// How the picture is currently seen
+    orientation: D4
 }
 
 /// Implementation of the [Debug](std::fmt::Debug) trait for
@@ -22,15 +29,12 @@ struct Picture {
 /// character in place. The referential is still raster.
 impl std::fmt::Debug for Picture {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        for y in 0..self.sidelen {
-            for x in 0..self.sidelen {
-                if let Err(k) = write!(f, "{}", self.data[&(y,x)]) {
-                    return Err(k);
-                }
-            }
-            if let Err(k) = writeln!(f,) {
-                return Err(k);
+        let (width, height) = self.size();
+        for y in 0..height {
+            for x in 0..width {
+                write!(f, "{}", self.get(y,x))?;
             }
+            writeln!(f,)?;
         }
         Ok(())
     }
@@ -40,81 +44,38 @@ impl Picture {
     /// Rotates the picture in place 90° to the right (when facing the picture).
     /// # Example
     ///
+    /// ```ignore
+    /// let mut pic: Picture = tileset.extract()?;
+    /// pic.rotate_right();
     /// ```
-    /// fn main() {
-    ///   // ...
-    ///   let mut pic: Picture = tileset.extract();
-    ///   pic.rotate_right();
-    /// }
-    /// ```
+    #[allow(dead_code)]
     fn rotate_right(&mut self) {
-        let nmap = (0..self.sidelen*self.sidelen)
-            .map(|x| {
-                let (col, row) = (x%self.sidelen, x/self.sidelen);
-                ((col,row), self.data[&(self.sidelen-1-row,col)])
-            })
-        .collect::<HashMap<(usize,usize),char>>();
-        self.data = nmap;
+        self.orientation = self.orientation.then(D4::rotation(1));
     }
     /// Flips the picture upside down, rotating 180° around the top-down axis.
     /// (This means that **l**eft and **r**ight get flipped)
     /// # Example
     ///
+    /// ```ignore
+    /// let mut pic: Picture = tileset.extract()?;
+    /// pic.flip_lr();
     /// ```
-    /// fn main() {
-    ///   // ...
-    ///   let mut pic: Picture = tileset.extract();
-    ///   pic.flip_lr();
-    /// }
-    /// ```
+    #[allow(dead_code)]
     fn flip_lr(&mut self) {
-        let nmap = (0..self.sidelen*self.sidelen)
-            .map(|x| {
-                let (col, row) = (x%self.sidelen, x/self.sidelen);
-                ((col,row), self.data[&(col,self.sidelen-1-row)])
-            })
-        .collect::<HashMap<(usize,usize),char>>();
-        self.data = nmap;
+        self.orientation = self.orientation.then(D4::FLIP_LR);
     }
-    /// Search for the pattern of the sea monster. If it cannot be found,
-    /// returns false. Returns true otherwise.
-    fn contains(&mut self) -> bool {
-        let seamonster = vec![
-            (0,18),
-            (1,0),(1,5),(1,6),(1,11),(1,12),(1,17),(1,18),(1,19),
-            (2,1),(2,4),(2,7),(2,10),(2,13),(2,16)
-        ];
-        for y in 0..self.sidelen-3 {
-            for x in 0..self.sidelen-20 {
-                if seamonster.iter()
-                    .all(|(dy,dx)| self.data[&(y+dy,x+dx)]=='#') {
-                        return true;
-                }
-            }
-        }
-        false
+    /// Returns the (width, height) of the picture as currently seen.
+    fn size(&self) -> (usize, usize) {
+        self.orientation.dims(self.width, self.height)
     }
-    /// Find all the sea monsters and replace their `'#'` with `'O'`.
-    /// When a sea monster is found, since the characters are
-    /// immediately replaced in-place, there cannot be any
-    /// overlap.
-    fn find_and_replace(&mut self) {
-        let seamonster = vec![
-            (0,18),
-            (1,0),(1,5),(1,6),(1,11),(1,12),(1,17),(1,18),(1,19),
-            (2,1),(2,4),(2,7),(2,10),(2,13),(2,16)
-        ];
-        for y in 0..self.sidelen-3 {
-            for x in 0..self.sidelen-20 {
-                if seamonster.iter()
-                    .any(|(dy,dx)| self.data[&(y+dy,x+dx)]!='#') {
-                    continue;
-                }
-                for (dy,dx) in &seamonster {
-                    self.data.insert((y+dy,x+dx), 'O');
-                }
-            }
-        }
+    /// Returns the character at `(row, col)` as currently seen.
+    fn get(&self, row: usize, col: usize) -> char {
+        self.data[&self.orientation.source((row, col), self.width, self.height)]
+    }
+    /// Replaces the character at `(row, col)` as currently seen.
+    fn set(&mut self, row: usize, col: usize, c: char) {
+        let cell = self.orientation.source((row, col), self.width, self.height);
+        self.data.insert(cell, c);
     }
     /// Returns the current count of `'#'` in the raw data.
     /// This is the final answer for Advent of Code, day 20 part 2.
@@ -0,0 +1,163 @@
+/// Grey level of a `'.'` of the bitmap
+const PAPER: u8 = 255;
+/// Grey level of a `'#'` of the bitmap, and of tile identifiers
+const INK: u8 = 0;
+/// Grey level of a `'#'` belonging to a sea monster
+const MONSTER: u8 = 80;
+/// Grey level of the lines between tiles
+const GRIDLINE: u8 = 160;
+
+/// Digits 0 to 9 in a 3×5 font, read row after row from the top left,
+/// the most significant bit first.
+const DIGITS: [u16; 10] = [
+    0b111_101_101_101_111, 0b010_110_010_010_111, 0b111_001_111_100_111,
+    0b111_001_111_001_111, 0b101_101_111_001_001, 0b111_100_111_001_111,
+    0b111_100_111_101_111, 0b111_001_001_001_001, 0b111_101_111_101_111,
+    0b111_101_111_001_111
+];
+
+/// # What to draw when rendering a [TileSet](TileSet)
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+struct RenderOptions {
+    /// Keep the borders of the tiles, which the picture drops
+    borders: bool,
+    /// Draw lines between the tiles, along with their identifiers when
+    /// they fit
+    gridlines: bool,
+    /// Highlight the sea monsters of the picture
+    monsters: bool,
+    /// Side, in pixels, of a cell of the bitmap
+    scale: usize
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        RenderOptions { borders: false, gridlines: false, monsters: false, scale: 1 }
+    }
+}
+
+/// # Greyscale image
+///
+/// Pixels are stored row after row, from the top left corner.
+struct Raster {
+    width: usize,
+    height: usize,
+    pixels: Vec<u8>
+}
+
+impl Raster {
+    /// A `width` by `height` image filled with `level`.
+    fn new(width: usize, height: usize, level: u8) -> Self {
+        Raster { width, height, pixels: vec![level; width*height] }
+    }
+    /// Returns the grey level at `(row, col)`.
+    #[allow(dead_code)]
+    fn get(&self, row: usize, col: usize) -> u8 {
+        self.pixels[row*self.width + col]
+    }
+    /// Paint the `width` by `height` rectangle whose top left corner is at
+    /// `(row, col)` with `level`.
+    fn fill(&mut self, (row, col): (usize, usize), width: usize, height: usize, level: u8) {
+        for y in row..row+height {
+            let start = y*self.width + col;
+            self.pixels[start..start+width].iter_mut().for_each(|p| *p = level);
+        }
+    }
+    /// Write `number` in the font of [DIGITS](DIGITS) on a blank box
+    /// whose top left corner is at `(row, col)`, if the box fits in a
+    /// `width` by `height` area. Returns whether it did.
+    fn label(&mut self, (row, col): (usize, usize), width: usize, height: usize, number: usize) -> bool {
+        let digits = number.to_string().bytes().map(|b| usize::from(b - b'0'))
+            .collect::<Vec<usize>>();
+        let (box_width, box_height) = (4*digits.len() + 1, 7);
+        if box_width > width || box_height > height {
+            return false;
+        }
+        self.fill((row, col), box_width, box_height, PAPER);
+        for (i, &digit) in digits.iter().enumerate() {
+            for bit in 0..15 {
+                if DIGITS[digit] >> (14 - bit) & 1 == 1 {
+                    self.fill((row + 1 + bit/3, col + 1 + 4*i + bit%3), 1, 1, INK);
+                }
+            }
+        }
+        true
+    }
+    /// Encode the image as a binary greymap (PGM, `P5`).
+    fn pgm(&self) -> Vec<u8> {
+        let mut res = format!("P5\n{} {}\n255\n", self.width, self.height).into_bytes();
+        res.extend(&self.pixels);
+        res
+    }
+    /// Encode the image as a binary bitmap (PBM, `P4`), where every pixel
+    /// darker than the gridlines is black.
+    fn pbm(&self) -> Vec<u8> {
+        let mut res = format!("P4\n{} {}\n", self.width, self.height).into_bytes();
+        for row in self.pixels.chunks(self.width.max(1)) {
+            // Rows are padded to whole bytes, the first pixel being the
+            // most significant bit
+            for byte in row.chunks(8) {
+                res.push(byte.iter().enumerate()
+                    .filter(|&(_, &level)| level <= GRIDLINE)
+                    .fold(0, |acc, (i, _)| acc | 0x80 >> i));
+            }
+        }
+        res
+    }
+}
+
+impl TileSet {
+    /// Render the assembled puzzle as a greyscale image.
+    ///
+    /// Every tile is drawn as it was laid, trimmed of its borders unless
+    /// they are asked for, so that the image without any option is the
+    /// [extracted](TileSet::extract) picture. Gridlines are one pixel
+    /// wide and go between the tiles only; the identifier of a tile is
+    /// written in its top left corner if it fits, which usually takes a
+    /// scale of 2 or more.
+    ///
+    /// This method also builds the puzzle by calling
+    /// [TileSet::build](TileSet::build).
+    ///
+    /// # Errors
+    ///
+    /// Returns a [SolveError](SolveError) when the puzzle cannot be built.
+    fn render(&mut self, options: RenderOptions) -> Result<Raster, SolveError> {
+        let mut picture = self.extract()?;
+        if options.monsters {
+            let monster = Pattern::sea_monster();
+            let matches = picture.find(&monster, true);
+            picture.highlight(&monster, &matches);
+        }
+        let size = self.tiles.values().next().map_or(0, |t| t.size);
+        let cells = if options.borders { 0..size } else { 1..size-1 };
+        let block = cells.len() * options.scale;
+        let gap = usize::from(options.gridlines);
+        let mut raster = Raster::new(
+            (self.width*(block + gap)).saturating_sub(gap),
+            (self.height*(block + gap)).saturating_sub(gap),
+            GRIDLINE
+        );
+        for (&(ty, tx), id) in &self.final_puzzle {
+            let tile = &self.tiles[id];
+            let origin = (ty*(block + gap), tx*(block + gap));
+            for row in cells.clone() {
+                for col in cells.clone() {
+                    let inner = row > 0 && row < size-1 && col > 0 && col < size-1;
+                    let level = if !tile.get(row, col) {
+                        PAPER
+                    } else if inner && picture.get(ty*(size-2) + row-1, tx*(size-2) + col-1) == 'O' {
+                        MONSTER
+                    } else { INK };
+                    let at = (origin.0 + (row - cells.start)*options.scale,
+                              origin.1 + (col - cells.start)*options.scale);
+                    raster.fill(at, options.scale, options.scale, level);
+                }
+            }
+            if options.gridlines {
+                raster.label(origin, block, block, *id);
+            }
+        }
+        Ok(raster)
+    }
+}
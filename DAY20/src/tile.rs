@@ -2,7 +2,8 @@
 ///
 /// This structure describes a tile in terms of its four current edges,
 /// stored following a precise order, and the input bitmap contained in
-/// the tile.
+/// the tile. Tiles are square, of any size; the day's input has them
+/// 10×10, as in the examples below.
 ///
 /// ## Edge representation
 /// This structure contains an array of four integers describing the pattern
@@ -40,59 +41,76 @@
 ///
 /// ## Bitmap storage
 ///
-/// The bitmap of a tile is stored in a [HashMap](std::collections::HashMap)
-/// of `bool` indexed by `(usize, usize)`, similarly to how a [Picture](Picture)
-/// works. In fact, had I written `Picture` in a modular fashion (and before
-/// writing the Tile structure), I would have delegated bitmap storage to a
-/// `Picture`.
+/// The bitmap of a tile is stored as one integer per row, the leftmost
+/// pixel being the most significant bit, so that the up edge is the first
+/// row as it is. Since edges are `usize`, so are rows.
+///
+/// The bitmap is kept as it was read. Rotating or flipping the tile only
+/// changes its [orientation](D4), through which the bitmap and the edges
+/// are seen.
 struct Tile {
-    /// The four edges currently shown by the tile.
+    /// The four edges of the tile as it was read.
     /// Order is up, left, down, right.
-    edges: [usize; 4],
-    /// Raw bitmap data for the current tile. Note that indexing follows
-    /// a typical raster referential.
-    data: HashMap<(usize,usize),bool>
+    raw_edges: [usize; 4],
+    /// Rows of the tile as it was read, from top to bottom, bit-packed.
+    rows: Vec<usize>,
+    /// How the tile is currently laid
+    orientation: D4,
+    /// Length of the sides of the square tile. Edges are read as integers
+    /// of that many bits, so it cannot exceed [MAX_TILE_SIZE](MAX_TILE_SIZE).
+    size: usize
 }
 
+/// Longest side a tile may have, edges being read as `usize`.
+const MAX_TILE_SIZE: usize = usize::BITS as usize;
+
 #[derive(Debug)]
 /// Error thrown while parsing a `Tile` from a `&str`.
-struct TileParseError;
+enum TileParseError {
+    /// The tile is not a square of `'.'` and `'#'`, at least 3 wide
+    Malformed,
+    /// The tile is wider than [MAX_TILE_SIZE](MAX_TILE_SIZE)
+    TooLarge
+}
 
 /// Parse a `&str` into a `Tile`.
 ///
 /// The input string must not contain the `Tile: <ID>` line.
 /// In my program, it is typically trimmed by the [TileSet](TileSet) that
 /// is being built, which itself calls this builder for its tiles.
+/// Tiles cannot be wider than [MAX_TILE_SIZE](MAX_TILE_SIZE).
 impl std::str::FromStr for Tile {
     /// Error thrown when parsing fails (although, well, it shouldn't).
     type Err = TileParseError;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        // Build a hashmap with coordinates for convenience
-        let grid = s.split('\n').enumerate()
-            .flat_map(|(row, x)| x.chars().enumerate()
-                 .map(|(col, y)| ((row,col), match y {
-                     '.' => false,
-                     '#' => true,
-                     _ => panic!("Weep")
-                 })).collect::<Vec<((usize,usize),bool)>>()
-            )
-            .collect::<HashMap<(usize,usize),bool>>();
+        let lines = s.split('\n').collect::<Vec<&str>>();
+        let size = lines.len();
+        if size > MAX_TILE_SIZE {
+            return Err(TileParseError::TooLarge);
+        }
+        if size < 3 || lines.iter().any(|l| l.chars().count() != size) {
+            return Err(TileParseError::Malformed);
+        }
+        let rows = lines.iter()
+            .map(|l| l.chars().try_fold(0, |acc, c| match c {
+                '.' => Ok(acc*2),
+                '#' => Ok(acc*2+1),
+                _ => Err(TileParseError::Malformed)
+            }))
+            .collect::<Result<Vec<usize>, TileParseError>>()?;
+        // Columns read top to bottom
+        let column = |col: usize| rows.iter()
+            .fold(0, |acc, r| acc*2 + (r >> (size-1-col) & 1));
         Ok(Tile {
-            edges: [
-                (0..10)
-                    .fold(0, |acc, b| if grid[&(0,b)] { acc*2+1 }
-                          else { acc*2 }),
-                (0..10).rev()
-                    .fold(0, |acc, b| if grid[&(b,0)] { acc*2+1 }
-                          else { acc*2 }),
-                (0..10).rev()
-                    .fold(0, |acc, b| if grid[&(9,b)] { acc*2+1 }
-                          else { acc*2 }),
-                (0..10)
-                    .fold(0, |acc, b| if grid[&(b,9)] { acc*2+1 }
-                          else { acc*2 })
+            orientation: D4::IDENTITY,
+            raw_edges: [
+                rows[0],
+                flip_side(column(0), size),
+                flip_side(rows[size-1], size),
+                column(size-1)
             ],
-            data: grid
+            rows,
+            size
         })
     }
 }
@@ -104,147 +122,104 @@ impl std::str::FromStr for Tile {
 /// currently shown by the tile, according to our pattern-reading convention.
 impl std::fmt::Debug for Tile {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let edges = self.edges();
         write!(f, "UP={},LEFT={},DOWN={},RIGHT={}",
-            self.edges[0], self.edges[1], self.edges[2],
-               self.edges[3])
+            edges[0], edges[1], edges[2], edges[3])
     }
 }
 
-/// Implementation of a conversion from `Tile` to `String`.
+/// Display a `Tile` as its grid.
 ///
 /// The output format is the grid with `'#'` and `'.'` where
-/// they need to be. All flips and rotations are taken into account,
-/// since they create modifications in-place of the tile.
-impl std::string::ToString for Tile {
-    fn to_string(&self) -> String {
-        let mut s = String::new();
-        for i in 0..10 {
-            for j in 0..10 {
-                s.push(if self.data[&(i,j)] { '#' } else { '.' });
-            }
-            if i < 9 { s.push('\n'); }
-        }
-        s
+/// they need to be, as seen in the current orientation.
+impl std::fmt::Display for Tile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let lines = (0..self.size).map(|i| self.line(i)).collect::<Vec<String>>();
+        write!(f, "{}", lines.join("\n"))
     }
 }
- 
-/// Performs a 10-bit integer bit reversal.
-fn flip_side(u: usize) -> usize {
-    // Bit size for usize isn't stable yet
-    // So instead of usize::BITS I'm using std::mem::size_of
-    u.reverse_bits() >> (std::mem::size_of::<usize>()*8-10)
+
+/// Performs a `size`-bit integer bit reversal.
+fn flip_side(u: usize, size: usize) -> usize {
+    u.reverse_bits() >> (usize::BITS as usize - size)
 }
 
 impl Tile {
     /// Rotate the tile 90° clockwise (when facing it).
-    /// This method updates the edges, but also the bitmap.
+    #[allow(dead_code)]
     fn rotate_right(&mut self) {
-        self.edges = [
-            self.edges[1],
-            self.edges[2],
-            self.edges[3],
-            self.edges[0]
-        ];
-        let nmap = (0..100).map(|x| {
-            let (col, row) = (x%10, x/10);
-            ((col,row), self.data[&(9-row,col)])
-        }).collect::<HashMap<(usize,usize),bool>>();
-        self.data = nmap;
+        self.orient(D4::rotation(1));
     }
     /// Rotate the tile 90° counter-clockwise (when facint it).
-    /// This method updates the edges, but also the bitmap.
+    #[allow(dead_code)]
     fn rotate_left(&mut self) {
-        self.edges = [
-            self.edges[3],
-            self.edges[0],
-            self.edges[1],
-            self.edges[2]
-        ];
-        let nmap = (0..100).map(|x| {
-            let (col, row) = (x%10, x/10);
-            ((col,row), self.data[&(row,9-col)])
-        }).collect::<HashMap<(usize,usize),bool>>();
-        self.data = nmap;
+        self.orient(D4::rotation(3));
     }
     /// Flip the tile by rotating 180° around the UP/DOWN axis.
     /// Note that the **l**eft and **r**ight edges are inverted.
-    /// This method updates the edges, but also the bitmap.
+    #[allow(dead_code)]
     fn flip_lr(&mut self) {
-        self.edges = [
-            flip_side(self.edges[0]),
-            flip_side(self.edges[3]),
-            flip_side(self.edges[2]),
-            flip_side(self.edges[1])
-        ];
-        let nmap = (0..100).map(|x| {
-            let (col, row) = (x%10, x/10);
-            ((col,row), self.data[&(col,9-row)])
-        }).collect::<HashMap<(usize,usize),bool>>();
-        self.data = nmap;
+        self.orient(D4::FLIP_LR);
     }
     /// Flip the tile by rotating 180° around the LEFT/RIGHT axis.
     /// Note that the **u**p and **d**own edges are inverted.
-    /// This method updates the edges, but also the bitmap.
+    #[allow(dead_code)]
     fn flip_ud(&mut self) {
-        self.edges = [
-            flip_side(self.edges[2]),
-            flip_side(self.edges[1]),
-            flip_side(self.edges[0]),
-            flip_side(self.edges[3])
-        ];
-        let nmap = (0..100).map(|x| {
-            let (col, row) = (x%10, x/10);
-            ((col,row), self.data[&(9-col,row)])
-        }).collect::<HashMap<(usize,usize),bool>>();
-        self.data = nmap;
+        self.orient(D4::FLIP_UD);
+    }
+    /// Turn the tile further by `d`.
+    fn orient(&mut self, d: D4) {
+        self.orientation = self.orientation.then(d);
+    }
+    /// Whether the pixel at `(row, col)` is set, in the current
+    /// orientation.
+    fn get(&self, row: usize, col: usize) -> bool {
+        let (row, col) = self.orientation.source((row, col), self.size, self.size);
+        self.rows[row] >> (self.size-1-col) & 1 == 1
+    }
+    /// Returns the four edges currently shown by the tile.
+    fn edges(&self) -> [usize; 4] {
+        self.oriented_edges(self.orientation)
     }
     /// Returns the pattern of the current up edge.
-    fn edge_up(&self) -> usize { self.edges[0] }
+    #[allow(dead_code)]
+    fn edge_up(&self) -> usize { self.edges()[0] }
     /// Returns the pattern of the current left edge.
-    fn edge_left(&self) -> usize { self.edges[1] }
+    #[allow(dead_code)]
+    fn edge_left(&self) -> usize { self.edges()[1] }
     /// Returns the pattern of the current down edge.
-    fn edge_down(&self) -> usize { self.edges[2] }
+    #[allow(dead_code)]
+    fn edge_down(&self) -> usize { self.edges()[2] }
     /// Returns the pattern of the current right edge.
-    fn edge_right(&self) -> usize { self.edges[3] }
-    /// Produce a [HashSet<usize>](std::collections::HashSet) of all the
-    /// possible edge patterns that are could be shown by flipping.
-    fn all_possible_edges(&self) -> HashSet<usize> {
-        self.edges.iter().flat_map(|x| vec![*x, flip_side(*x)])
-            .collect::<HashSet<usize>>()
-    }
-    /// Produce a [HashSet<usize>](std::collections::HashSet) of all the
-    /// current edge patterns that are shown.
-    fn all_current_edges(&self) -> HashSet<usize> {
-        self.edges.iter().copied().collect::<HashSet<usize>>()
-    }
-    /// Returns a boolean describing whether or not the current tile
-    /// can show a pattern given as argument on any of its side.
-    /// # Arguments
-    ///  - `u` a [usize](usize) containing the pattern wanted.
-    fn can_show(&self, u: usize) -> bool {
-        self.edges.iter().flat_map(|x| vec![*x, flip_side(*x)])
-            .any(|x| x == u)
-    }
-    /// Returns a boolean describing whether or not the current tile
-    /// currently shows a pattern given as argument on any of its side.
-    /// # Arguments
-    ///  - `u` a [usize](usize) containing the pattern wanted.
-    fn shows(&self, u: usize) -> bool {
-        self.edges.iter().find(|&x| *x == u).is_some()
+    #[allow(dead_code)]
+    fn edge_right(&self) -> usize { self.edges()[3] }
+    /// Returns the four edges [up, left, down, right] the tile shows when
+    /// laid in orientation `d`, from the way it was read.
+    ///
+    /// Rotating right moves every edge one place back in the array; a
+    /// flip swaps left and right, and reverses every pattern.
+    fn oriented_edges(&self, d: D4) -> [usize; 4] {
+        let e = self.raw_edges;
+        let e = if d.flip {
+            [flip_side(e[0], self.size), flip_side(e[3], self.size),
+             flip_side(e[2], self.size), flip_side(e[1], self.size)]
+        } else { e };
+        let r = usize::from(d.rotations);
+        [e[r], e[(r+1)%4], e[(r+2)%4], e[(r+3)%4]]
     }
     /// Returns a [String](String) containing the `u`th line of the current
     /// tile.
     ///
     /// # Arguments
     ///  - `u` a [usize](usize) which is the number of the line wanted. The
-    ///  top-most line is at `u=0` and the bottom line is at `u=9`.
+    ///    top-most line is at `u=0` and the bottom line is at `u=size-1`.
     ///
     /// # Panics
     ///
-    /// Will panic if `u >= 10` (yes, this is hardcoded).
+    /// Will panic if `u >= size`.
     fn line(&self, u: usize) -> String {
-        assert!(u < 10);
-        (0..10).map(|x| if self.data[&(u,x)] {
+        assert!(u < self.size);
+        (0..self.size).map(|x| if self.get(u,x) {
             "#" 
         } else { "." }).collect::<Vec<&str>>().join("")
     }
@@ -253,14 +228,14 @@ impl Tile {
     ///
     /// # Arguments
     ///  - `u` a [usize](usize) which is the number of the line wanted. The
-    ///  top-most line is at `u=0` and the bottom line is at `u=9`.
+    ///    top-most line is at `u=0` and the bottom line is at `u=size-1`.
     ///
     /// # Panics
     ///
-    /// Will panic if `u >= 10` (yes, this is hardcoded).
+    /// Will panic if `u >= size`.
     fn trimmed_line(&self, u: usize) -> String {
-        assert!(u < 10);
-        (1..9).map(|x| if self.data[&(u,x)] {
+        assert!(u < self.size);
+        (1..self.size-1).map(|x| if self.get(u,x) {
             "#"
         } else { "." }).collect::<Vec<&str>>().join("")
     }
@@ -1,6 +1,10 @@
 // Those already import HashMap for us
+include!("orientation.rs");
 include!("picture.rs");
 include!("tile.rs");
+include!("pattern.rs");
+include!("render.rs");
+include!("generate.rs");
 
 /// # A set of `Tile`
 ///
@@ -10,15 +14,20 @@ include!("tile.rs");
 struct TileSet {
     /// The tiles themselves indexed by their identifier.
     tiles: HashMap<usize, Tile>,
-    /// A vec that contains, for every tile, the different edge patterns they
-    /// can show. Of course, most patterns will appear twice or more.
-    /// This vec is built in order to find edges that only appear once (i.e.
-    /// the borders of the puzzle).
-    all_possible_edges: Vec<usize>,
+    /// The sides of the tiles, as `(identifier, side)` with sides numbered
+    /// up, left, down, right, indexed by their [canonical](canonical_edge)
+    /// pattern. Sides that fit each other share an entry, and those alone
+    /// in theirs are the borders of the puzzle.
+    /// This is built using [TileSet::build_edge_index](TileSet::build_edge_index).
+    edge_index: HashMap<usize, Vec<(usize, usize)>>,
     /// A picture of the final puzzle once it is built, indexed in a raster
     /// referential, where the values are the identifiers of the tiles.
     /// This is built using [TileSet::build](TileSet::build).
     final_puzzle: HashMap<(usize,usize),usize>,
+    /// Number of tiles in a row of the final puzzle, once built
+    width: usize,
+    /// Number of tiles in a column of the final puzzle, once built
+    height: usize,
 }
 
 /// Error thrown when parsing from `&str` to `TileSet` fails.
@@ -32,7 +41,7 @@ struct TileSetParseError;
 /// storing it, and then using the implementation of `FromStr`
 /// for `Tile` to obtain a tile. The `HashMap` of tiles is built
 /// along the way and the resulting `TileSet` is returned if all
-/// goes well.
+/// goes well. All tiles must have the same size.
 impl std::str::FromStr for TileSet {
     /// Error thrown when parsing from `&str` to `TileSet` fails.
     type Err = TileSetParseError;
@@ -53,10 +62,18 @@ impl std::str::FromStr for TileSet {
                 return Err(TileSetParseError{});
             }
         }
+        let mut sizes = tiles.values().map(|t| t.size);
+        if let Some(size) = sizes.next() {
+            if sizes.any(|s| s != size) {
+                return Err(TileSetParseError{});
+            }
+        }
         Ok(TileSet {
             tiles,
             final_puzzle: HashMap::new(),
-            all_possible_edges: Vec::new(),
+            edge_index: HashMap::new(),
+            width: 0,
+            height: 0,
         })
     }
 }
@@ -73,13 +90,9 @@ impl std::fmt::Debug for TileSet {
         let mut newline = false;
         for (id, tile) in &self.tiles {
             if newline {
-                if let Err(k) = writeln!(f,) {
-                    return Err(k);
-                }
+                writeln!(f,)?;
             } else { newline = true; }
-            if let Err(u) = write!(f, "[{}]{:?}", id, tile) {
-                return Err(u);
-            }    
+            write!(f, "[{}]{:?}", id, tile)?;
         }
         Ok(())
     }
@@ -87,341 +100,153 @@ impl std::fmt::Debug for TileSet {
 
 /// Implementation of a `TileSet`.
 impl TileSet {
-    /// Build all of the edges that can be shown by every tile,
-    /// accounting for duplicates across tiles and keeping them.
-    /// The `Vec` created is used later 
-    fn build_all_possible_edges(&mut self) {
-        self.all_possible_edges = self.tiles.values()
-            .flat_map(Tile::all_possible_edges)
-            .collect::<Vec<usize>>();
-    }
-    /// Get the tile identifier of the four corner tiles.
-    /// Requires that the possible edges vector be built,
-    /// or it will return an empty `HashSet`.
-    fn get_corners(&self) -> HashSet<usize> {
-        self.tiles.iter().filter_map(|(&k,tile)|
-            if tile.all_possible_edges().iter()
-                .filter(|x|
-                        self.all_possible_edges.iter()
-                        .filter(|y| y==x).count() == 1).count() == 4 {
-                    Some(k)
-                } else { None }
-        ).collect::<HashSet<usize>>()
-    }
-    /// Get the tile identifier of the pure edges (i.e. corner excluded).
-    /// Requires that the possible edges vector be built, or it will
-    /// return an empty `HashSet`.
-    fn get_pure_edges(&self) -> HashSet<usize> {
-        self.tiles.iter().filter_map(|(&k,tile)| 
-            if tile.all_possible_edges().iter()
-                .filter(|x|
-                        self.all_possible_edges.iter()
-                        .filter(|y| y==x).count() == 1).count() == 2 {
-                    Some(k)
-                } else { None }
-        ).collect::<HashSet<usize>>()
-    }
-    /// Get the edges of a tile which identifier is given that are borders
-    /// of the puzzle.
-    ///
-    /// # Arguments
-    ///
-    ///  - `c` : the tile's identifier as a `usize`.
-    ///
-    /// Requires that the possible edges vector be built, or it will
-    /// return an empty `HashSet`.
-    fn get_unique_edges(&self, c: usize) -> HashSet<usize> {
-        self.tiles[&c].all_possible_edges()
-            .iter().filter(|x|
-                self.all_possible_edges.iter()
-                    .filter(|y| y==x).count() == 1)
-            .copied().collect()
-    }
-    /// Rotate a tile which identifier is given as argument in-place.
-    ///
-    /// # Arguments
-    ///
-    ///  - `c` : the identifier of the tile to get.
-    ///
-    /// # Panics
-    ///
-    /// Will panic if the required tile does not exist.
-    fn rotate_right(&mut self, c: usize) {
-        if let Some(tile) = self.tiles.get_mut(&c) {
-            tile.rotate_right();    
-        }
-    }
-    /*fn rotate_left(&mut self, c: usize) {
-        if let Some(tile) = self.tiles.get_mut(&c) {
-            tile.rotate_left();
+    /// Index the sides of every tile by their pattern, in time linear in
+    /// the number of tiles.
+    fn build_edge_index(&mut self) {
+        self.edge_index.clear();
+        for (&id, tile) in &self.tiles {
+            for (side, &edge) in tile.raw_edges.iter().enumerate() {
+                self.edge_index.entry(canonical_edge(edge, tile.size))
+                    .or_default().push((id, side));
+            }
         }
     }
-    fn flip_lr(&mut self, c: usize) {
-        if let Some(tile) = self.tiles.get_mut(&c) {
-            tile.flip_lr();
-        }
+    /// Whether no other tile can show `edge` of a tile of `size`.
+    /// Requires that the edge index be built.
+    #[allow(dead_code)]
+    fn is_unique(&self, edge: usize, size: usize) -> bool {
+        self.edge_index.get(&canonical_edge(edge, size)).is_none_or(|sides| sides.len() == 1)
     }
-    fn flip_ud(&mut self, c: usize) {
-        if let Some(tile) = self.tiles.get_mut(&c) {
-            tile.flip_ud();
+    /// Number of sides of every tile that match no other tile.
+    /// Requires that the edge index be built.
+    fn unmatched_sides(&self) -> HashMap<usize, usize> {
+        let mut res = self.tiles.keys().map(|&id| (id, 0)).collect::<HashMap<usize, usize>>();
+        for sides in self.edge_index.values().filter(|sides| sides.len() == 1) {
+            *res.entry(sides[0].0).or_insert(0) += 1;
         }
-    }*/
-    /// Place the given piece at the given position with the current rotation
-    /// it has in memory
-    fn put_in_place(&mut self, y: usize, x: usize, tile_id: usize) {
-        self.final_puzzle.insert((y,x), tile_id);
+        res
     }
-    /// Prepare the corners by determining their unique
-    /// edges and rotating/flipping until those are on the top
-    /// and left sides
-    fn prepare_corners(&mut self) {
-        for corner in self.get_corners() {
-            let unique_edges = self.get_unique_edges(corner);
-            //println!("[{:?}] unique={:?}", corner, unique_edges);
-            while !unique_edges.contains(&self.tiles[&corner].edge_up())
-                || !unique_edges.contains(&self.tiles[&corner].edge_left()) {
-                    //println!("CURRENT={:?}", self.tiles[&corner]);
-                    self.rotate_right(corner);
-            }
-            //println!("CURRENT={:?}", self.tiles[&corner]);
-        }
+    /// Get the tile identifier of the four corner tiles, which are the
+    /// ones with two sides matching no other tile.
+    /// Requires that the edge index be built,
+    /// or it will return an empty `HashSet`.
+    fn get_corners(&self) -> HashSet<usize> {
+        self.unmatched_sides().into_iter()
+            .filter_map(|(id, n)| if n == 2 { Some(id) } else { None })
+            .collect::<HashSet<usize>>()
     }
-    /// Prepare all of the pure edges of the tile set.
+    /// Candidate (width, height) of the final puzzle, in the order
+    /// [TileSet::build](TileSet::build) tries them.
     ///
-    /// The unique edge of every one of those tiles is placed up.
-    fn prepare_edges(&mut self) {
-        for edge_tile in self.get_pure_edges() {
-            let unique_edges = self.get_unique_edges(edge_tile);
-            while !unique_edges.contains(&self.tiles[&edge_tile].edge_up()) {
-                self.rotate_right(edge_tile);
-            }
+    /// The order is only a heuristic. On a puzzle whose inner edges all
+    /// match exactly two tiles, a layout of `W × H` tiles has 4 corners
+    /// showing two unique edges and `2(W-2) + 2(H-2)` other border tiles
+    /// showing one, or 2 ends showing three and `H-2` tiles showing two
+    /// when `W` is 1. Layouts come by how many tiles they would count
+    /// differently from the tiles themselves, closest to a square first
+    /// among equals. Edge patterns shared by several tiles blur those
+    /// counts, and may well put a wrong layout first. A layout and its
+    /// transpose are the same up to a rotation, so only the one with
+    /// `W <= H` is given.
+    /// Requires that the edge index be built.
+    fn layouts(&self) -> Vec<(usize, usize)> {
+        let n = self.tiles.len();
+        // Number of tiles showing 0 to 4 unique edges
+        let mut counted = [0; 5];
+        for sides in self.unmatched_sides().into_values() {
+            counted[sides] += 1;
         }
+        let expected = |w: usize, h: usize| {
+            let mut res = [0; 5];
+            match (w, h) {
+                (1, 1) => res[4] = 1,
+                (1, _) => { res[3] = 2; res[2] = h - 2; },
+                _ => { res[2] = 4; res[1] = 2 * (w - 2) + 2 * (h - 2); }
+            }
+            res[0] = n - res.iter().sum::<usize>();
+            res
+        };
+        let mut layouts = (1..=n).filter(|w| n.is_multiple_of(*w) && w * w <= n)
+            .map(|w| (w, n / w)).collect::<Vec<(usize, usize)>>();
+        layouts.sort_by_key(|&(w, h)| (
+            expected(w, h).iter().zip(&counted).map(|(&e, &c)| e.abs_diff(c)).sum::<usize>(),
+            h - w
+        ));
+        layouts
     }
-    // Build the damn puzzle
     /// Builds the puzzle.
     ///
-    /// This method is particularly slow and badly written. It calls every
-    /// other method that needs to be called beforehand (in order) :
-    ///  - [TileSet::build_all_possible_edges](TileSet::build_all_possible_edges)
-    ///  - [TileSet::prepare_corners](TileSet::prepare_corners)
-    ///  - [TileSet::prepare_edges](TileSet::prepare_edges)
+    /// The search grows the puzzle around one tile, laid as it is: every
+    /// solution can be turned so that this tile is, and every tile is in
+    /// every solution. The tiles placed always make a rectangle, or a
+    /// rectangle with one row or column being filled along one of its
+    /// sides, which must then be completed first: the search fills the
+    /// position of that row or column that the fewest unused tiles fit,
+    /// in any of their eight [orientations](D4), and backtracks as soon
+    /// as one of them cannot be filled. Once the rectangle is complete,
+    /// it is extended on one of its sides, longest first, as long as it
+    /// still fits the [layout](TileSet::layouts) tried. A side beyond the
+    /// borders of the puzzle is usually dropped after a tile or two, even
+    /// when edge patterns are shared by several tiles.
     ///
-    /// It then uses a naive puzzle solving algorithm to build the puzzle
-    /// by examining tile after tile and placing them one after another,
-    /// row after row, starting from the top left edge of the puzzle.
+    /// Every layout is first tried for [QUICK_STEPS](QUICK_STEPS)
+    /// placements per tile, in turn, which is plenty for the right one
+    /// and cuts off the wrong ones early. Only the layouts that were not
+    /// ruled out are then searched in full, each for at most
+    /// [SEARCH_STEPS](SEARCH_STEPS) placements per tile.
     ///
-    /// This method is absurdly slow, and big, and clunky, but I do not care.
-    /// I spent six hours writing it again and again until it worked without
-    /// crashing.
+    /// Once a solution is found, it is turned so that the puzzle is no
+    /// wider than high, every tile is laid in the orientation found, and
+    /// its identifier recorded in the final puzzle. Building again gives
+    /// the same result.
     ///
-    /// This method only works thanks to the property of today's input that
-    /// ***for a fixed starting piece, at every point, there is only one piece
-    /// that can fix the constraints of its neighbours and, if any, the
-    /// constraint of being a border piece***.
+    /// # Errors
     ///
-    /// # Panics
-    ///
-    /// Will panic under a wide array of circumstances, most of which are
-    /// undetermined, some of which may be more likely than you think.
-    fn build(&mut self) {
-        // Compute all the edges across tiles
-        self.build_all_possible_edges();
-        // Prepare all corners by aligning them to the left/up
-        self.prepare_corners();
-        // Prepare all of the pure edges, aligning the edge up
-        self.prepare_edges();
-        //println!("BUILDING A PUZZLE OF {} PIECES", self.tiles.len());
-        let sidelen = (self.tiles.len() as f64).sqrt() as usize;
-        // Edge to adhere to on the up side
-        // None means that it has to be a unique edge
-        let mut up_side_constraint: Vec<Option<usize>> = (0..sidelen)
-            .map(|_| None).collect();
-        // Edge to adhere to on the left side
-        // Again None means it's a unique edge
-        let mut left_side_constraint: Option<usize>;
-
-        // The structures we can pre-build
-        let mut corner_ids = self.get_corners();
-        assert_eq!(corner_ids.len(), 4);
-        let mut pure_edges = self.get_pure_edges();
-        assert_eq!(pure_edges.len(), 4*sidelen-8);
-        let mut other_pieces = self.tiles.keys()
-            .filter(|x| !corner_ids.contains(&x)
-                    && !pure_edges.contains(&x)).copied()
-            .collect::<HashSet<usize>>();
-        //assert_eq!(other_pieces.len(), (sidelen-2)*(sidelen-2));
-        //println!("PURE EDGES={:?}", pure_edges);
-
-        // Build
-        let mut placed: HashSet<usize> = HashSet::new();
-        for row in 0..sidelen {
-            left_side_constraint = None;
-            for col in 0..sidelen {
-                //println!("PLACING ({},{})", row, col);
-                //println!("CONSTRAINTS:L={:?},U={:?}", left_side_constraint,
-                         //up_side_constraint[col]);
-                //println!("Other pieces={:?}", other_pieces);
-                let chosen: Option<usize>;
-                match (left_side_constraint,
-                       up_side_constraint[col],
-                       row+1==sidelen, col+1==sidelen) {
-                    (None,None,false,false) => {
-                        // Pick a corner
-                        let cid = *corner_ids.iter().next().unwrap();
-                        corner_ids.remove(&cid);
-                        chosen = Some(cid);
-                    },
-                    (Some(left),None,false,false) => {
-                        // Pick a top edge
-                        let id = *pure_edges.iter()
-                            .find(|&e| {
-                                let ues = self.get_unique_edges(*e);
-                                let tile = self.tiles.get_mut(e).unwrap();
-                                if !tile.can_show(left) { return false; }
-                                if !tile.shows(left) { tile.flip_lr(); }
-                                ues.contains(&tile.edge_up())
-                                    && tile.edge_left() == left
-                            }).unwrap();
-                        chosen = Some(id);
-                        pure_edges.remove(&id);
-                    },
-                    (Some(left),None,false,true) => {
-                        // Pick the top right corner
-                        let corner = *corner_ids.iter().find(|&e| {
-                            let unes = self.get_unique_edges(*e);
-                            let tile = self.tiles.get_mut(e).unwrap();
-                            if !tile.can_show(left) { return false; }
-                            while !tile.shows(left) {
-                                tile.flip_lr();
-                                tile.rotate_left();
-                            }
-                            tile.rotate_right();
-                            unes.contains(&tile.edge_up())
-                                && unes.contains(&tile.edge_right())
-                                && tile.edge_left() == left
-                        }).unwrap();
-                        chosen = Some(corner);
-                        corner_ids.remove(&corner);
-                    },
-                    (None,Some(up),false,false) => {
-                        // Pure edge left
-                        let id = *pure_edges.iter()
-                            .find(|e| {
-                                let tile = self.tiles.get_mut(e).unwrap();
-                                let u = tile.all_possible_edges();
-                                if u.contains(&up) {
-                                    if !tile.all_current_edges()
-                                        .contains(&up) {
-                                             tile.flip_lr();
-                                    };
-                                    tile.rotate_left();
-                                    tile.edge_up() == up
-                                } else {
-                                    false
-                                }
-                            }).unwrap();
-                        chosen = Some(id);
-                        pure_edges.remove(&id);
-                    },
-                    (Some(left),Some(up),false,false) => {
-                        // Center piece
-                        let id = *other_pieces.iter().find(|e|{
-                            let tile = self.tiles.get_mut(&e).unwrap();
-                            if !tile.can_show(up) || !tile.can_show(left) {
-                                return false;
-                            }
-                            if !tile.shows(left) { tile.flip_lr(); }
-                            while tile.edge_left() != left
-                            { tile.rotate_left(); }
-                            tile.edge_up() == up && tile.edge_left() == left
-                        }).unwrap();
-                        chosen = Some(id);
-                        other_pieces.remove(&id);
-                    },
-                    (Some(left),Some(up),false,true) => {
-                        // Pure right edge
-                        let id = *pure_edges.iter().find(|e| {
-                            let tile = self.tiles.get_mut(e).unwrap();
-                            if !tile.can_show(left)
-                                || !tile.can_show(up) {
-                                    return false;
-                            }
-                            if !tile.shows(left) {
-                                tile.flip_lr();
-                            }
-                            // If we found the one, rotate it right first
-                            tile.rotate_right();
-                            // It should already be in place
-                            tile.edge_up() == up
-                                && tile.edge_left() == left
-                        }).unwrap();
-                        chosen = Some(id);
-                        pure_edges.remove(&id);
-                    },
-                    (None,Some(up),true,false) => {
-                        // Down left corner
-                        let id = *corner_ids.iter().find(|e| {
-                            let tile = self.tiles.get_mut(e).unwrap();
-                            if !tile.can_show(up) { return false; }
-                            // If the up constraint isn't there, flip
-                            if !tile.shows(up) {
-                                tile.flip_ud();
-                                tile.rotate_right();
-                            }
-                            // Flip left to align border
-                            tile.rotate_left();
-                            tile.edge_up() == up
-                        }).unwrap();
-                        chosen = Some(id);
-                        corner_ids.remove(&id);
-                    },
-                    (Some(left),Some(up),true,false) => {
-                        // Pure down edge
-                        let id = *pure_edges.iter()
-                            .find(|&e| {
-                                let u = self.get_unique_edges(*e);
-                                let tile = self.tiles.get_mut(&e).unwrap();
-                                if !tile.can_show(left)
-                                    || !tile.can_show(up)
-                                { return false; }
-                                if !tile.shows(left) { tile.flip_lr(); }
-                                (0..2).for_each(|_| tile.rotate_right());
-                                tile.edge_up() == up
-                                    && tile.edge_left() == left
-                                    && u.contains(&tile.edge_down())
-                            }).unwrap();
-                        chosen = Some(id);
-                        pure_edges.remove(&id);
+    /// Returns a [SolveError](SolveError) when there are no tiles, when
+    /// no arrangement makes all of their edges fit, or when the search
+    /// gave up before finding out.
+    fn build(&mut self) -> Result<(), SolveError> {
+        if self.tiles.is_empty() {
+            return Err(SolveError::Empty);
+        }
+        self.build_edge_index();
+        let mut layouts = self.layouts();
+        for &steps in &[QUICK_STEPS, SEARCH_STEPS] {
+            let mut open = Vec::new();
+            for &layout in &layouts {
+                let mut solver = Solver::new(self, layout, steps);
+                match solver.run() {
+                    Ok(true) => {
+                        self.lay(&solver);
+                        return Ok(());
                     },
-                    (Some(left),Some(up),true,true) => {
-                        // The last piece of the puzzle
-                        let last = corner_ids.iter().next().unwrap();
-                        let tile = self.tiles.get_mut(&last).unwrap();
-                        if !tile.shows(left) {
-                            tile.flip_lr();
-                            tile.rotate_left();
-                        }
-                        // Rotate to put it in place
-                        (0..2).for_each(|_| tile.rotate_right());
-                        // Check constraints
-                        if tile.edge_up() == up
-                            && tile.edge_left() == left {
-                                chosen = Some(*last);
-                        } else { panic!("so close"); }
-                    }
-                    _ => panic!("NIY")
-                }
-                if let Some(id) = chosen {
-                    placed.insert(id);
-                    let tile = &self.tiles[&id];
-                    left_side_constraint = Some(flip_side(tile.edge_right()));
-                    up_side_constraint[col] = Some(flip_side(tile.edge_down()));
-                    self.put_in_place(row, col, id);
-                } else {
-                    panic!("NOTHING WAS PLACED");
+                    Ok(false) => (),
+                    Err(_) => open.push(layout)
                 }
             }
+            layouts = open;
         }
+        Err(if layouts.is_empty() { SolveError::NoSolution } else { SolveError::GaveUp })
+    }
+    /// Record the solution found by a [Solver](Solver) as the final
+    /// puzzle, turned so that it is no wider than high.
+    fn lay(&mut self, solver: &Solver) {
+        let (width, height) = solver.dims();
+        // Any orientation swapping the sides will do
+        let turn = if width > height {
+            D4::all().find(|d| d.dims(width, height) == (height, width)).unwrap_or(D4::IDENTITY)
+        } else { D4::IDENTITY };
+        self.final_puzzle.clear();
+        for (&(row, col), &(id, o)) in &solver.grid {
+            let cell = ((row - solver.top) as usize, (col - solver.left) as usize);
+            if let Some(tile) = self.tiles.get_mut(&id) {
+                tile.orientation = D4::from_index(o).then(turn);
+            }
+            self.final_puzzle.insert(turn.apply(cell, width, height), id);
+        }
+        let (width, height) = turn.dims(width, height);
+        self.width = width;
+        self.height = height;
     }
     /// Extract a `Picture` from the built puzzle.
     /// 
@@ -430,30 +255,272 @@ impl TileSet {
     ///
     /// This method also builds the puzzle by calling
     /// [TileSet::build](TileSet::build).
-    fn extract(&mut self) -> Picture {
+    ///
+    /// # Errors
+    ///
+    /// Returns a [SolveError](SolveError) when the puzzle cannot be built.
+    fn extract(&mut self) -> Result<Picture, SolveError> {
         let mut s: HashMap<(usize,usize),char> = HashMap::new();
-        self.build();
-        let sidelen = (self.tiles.len() as f64).sqrt() as usize;
+        self.build()?;
+        let size = self.tiles.values().next().map_or(0, |t| t.size);
         let mut x: usize = 0;
         let mut y: usize = 0;
-        for row in 0..sidelen*10 {
-            if row%10 == 0 || row%10 == 9 { continue; }
-            for col in 0..sidelen {
-                let line = self.tiles[&self.final_puzzle[&(row/10,col)]]
-                    .trimmed_line(row%10);
-                //s.push(' ');
+        for row in 0..self.height*size {
+            if row%size == 0 || row%size == size-1 { continue; }
+            for col in 0..self.width {
+                let line = self.tiles[&self.final_puzzle[&(row/size,col)]]
+                    .trimmed_line(row%size);
                 for c in line.chars() {
                     s.insert((y,x), c);
                     x += 1;
                 }
-                //s.push_str(line.as_str());
             }
             x = 0;
             y += 1;
-            //s.push('\n');
-            //if row%10 == 9 { s.push('\n'); }
         }
-        Picture { data: s, sidelen: y }
+        Ok(Picture { data: s, width: self.width*(size-2), height: y, orientation: D4::IDENTITY })
+    }
+}
+
+/// The pattern standing for both `edge` and its flip, which are the two
+/// ways a side of a tile of `size` can be read.
+fn canonical_edge(edge: usize, size: usize) -> usize {
+    edge.min(flip_side(edge, size))
+}
+
+/// Error thrown when a `TileSet` cannot be assembled.
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+enum SolveError {
+    /// There are no tiles to assemble
+    Empty,
+    /// No arrangement of the tiles has all of their edges fitting
+    NoSolution,
+    /// The search ran out of steps before finding an arrangement
+    GaveUp
+}
+
+impl std::fmt::Display for SolveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SolveError::Empty => write!(f, "no tiles"),
+            SolveError::NoSolution => write!(f, "no solution"),
+            SolveError::GaveUp => write!(f, "gave up searching")
+        }
     }
 }
 
+/// Placements the search of [TileSet::build](TileSet::build) may try for
+/// every tile of a layout before giving up on it.
+const SEARCH_STEPS: usize = 1000;
+/// Placements the first, quick search of [TileSet::build](TileSet::build)
+/// may try for every tile of a layout before moving on to the next one.
+const QUICK_STEPS: usize = 10;
+
+/// A position of the puzzle being built, as `(row, col)` from the first
+/// tile placed, which may be negative.
+type Cell = (isize, isize);
+
+/// # Backtracking state of [TileSet::build](TileSet::build)
+///
+/// A placement is a tile identifier along with the [index](D4::index) of
+/// its orientation. Sides are numbered up, left, down, right, as edges
+/// are.
+struct Solver {
+    /// Size of the tiles
+    size: usize,
+    /// Number of tiles to place
+    total: usize,
+    /// The (width, height) the puzzle may take, either way round
+    layout: (usize, usize),
+    /// The edges (up, left, down, right) of every tile in every orientation
+    edges: HashMap<usize, [[usize; 4]; ORIENTATIONS]>,
+    /// For every side, the placements showing a given pattern on it
+    by_side: [HashMap<usize, Vec<(usize, usize)>>; 4],
+    /// The tile placed first
+    anchor: usize,
+    used: HashSet<usize>,
+    /// Placements made so far
+    grid: HashMap<Cell, (usize, usize)>,
+    /// Bounds of the rectangle being filled, inclusive
+    top: isize,
+    left: isize,
+    bottom: isize,
+    right: isize,
+    /// The row or column added to the rectangle last
+    strip: Vec<Cell>,
+    /// The sides the rectangle was found not to grow on, by
+    /// [Solver::extend](Solver::extend)
+    closed: [bool; 4],
+    /// Placements left to try before giving up
+    steps: usize
+}
+
+impl Solver {
+    /// Index the edges of every tile of a `TileSet`, to lay them out as
+    /// `layout` in at most `steps` placements per tile
+    fn new(set: &TileSet, layout: (usize, usize), steps: usize) -> Self {
+        let mut ids = set.tiles.keys().copied().collect::<Vec<usize>>();
+        ids.sort_unstable();
+        let edges = ids.iter()
+            .map(|&id| {
+                let tile = &set.tiles[&id];
+                let mut all = [[0; 4]; ORIENTATIONS];
+                for d in D4::all() {
+                    all[d.index()] = tile.oriented_edges(d);
+                }
+                (id, all)
+            })
+            .collect::<HashMap<usize, [[usize; 4]; ORIENTATIONS]>>();
+        let mut by_side: [HashMap<usize, Vec<(usize, usize)>>; 4] = Default::default();
+        for &id in &ids {
+            for (o, shown) in edges[&id].iter().enumerate() {
+                for (index, &pattern) in by_side.iter_mut().zip(shown) {
+                    index.entry(pattern).or_default().push((id, o));
+                }
+            }
+        }
+        Solver {
+            size: set.tiles.values().next().map_or(0, |t| t.size),
+            total: ids.len(),
+            layout, edges, by_side,
+            anchor: ids[0],
+            used: HashSet::new(),
+            grid: HashMap::new(),
+            top: 0, left: 0, bottom: 0, right: 0,
+            strip: Vec::new(),
+            closed: [false; 4],
+            steps: steps * ids.len()
+        }
+    }
+
+    /// The (width, height) of the rectangle being filled
+    fn dims(&self) -> (usize, usize) {
+        ((self.right - self.left + 1) as usize, (self.bottom - self.top + 1) as usize)
+    }
+
+    /// Whether a rectangle of `width` by `height` fits the layout, either
+    /// way round, exactly so in the directions closed on both sides
+    fn fits(&self, width: usize, height: usize) -> bool {
+        let fixed = (self.closed[1] && self.closed[3], self.closed[0] && self.closed[2]);
+        let (w, h) = self.layout;
+        [(w, h), (h, w)].iter().any(|&(w, h)| width <= w && height <= h
+            && (!fixed.0 || width == w) && (!fixed.1 || height == h))
+    }
+
+    /// The unused placements fitting every placed neighbour of `cell`
+    fn domain(&self, (row, col): Cell) -> Vec<(usize, usize)> {
+        // The pattern needed on each side to fit what is placed there
+        let needed = [(row - 1, col), (row, col - 1), (row + 1, col), (row, col + 1)].iter()
+            .enumerate()
+            .filter_map(|(side, next)| self.grid.get(next)
+                .map(|&(id, o)| (side, flip_side(self.edges[&id][o][(side + 2) % 4], self.size))))
+            .collect::<Vec<(usize, usize)>>();
+        let shortest = needed.iter()
+            .map(|&(side, pattern)| self.by_side[side].get(&pattern).map_or(&[][..], Vec::as_slice))
+            .min_by_key(|candidates| candidates.len())
+            .unwrap_or(&[]);
+        shortest.iter()
+            .filter(|(id, o)| !self.used.contains(id)
+                && needed.iter().all(|&(side, pattern)| self.edges[id][*o][side] == pattern))
+            .copied()
+            .collect()
+    }
+
+    /// Lay the anchor and search for a solution, returning whether there
+    /// is one.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SolveError::GaveUp` when the search runs out of steps.
+    fn run(&mut self) -> Result<bool, SolveError> {
+        self.grid.insert((0, 0), (self.anchor, D4::IDENTITY.index()));
+        self.used.insert(self.anchor);
+        self.solve()
+    }
+
+    /// Fill the rest of the puzzle, returning whether it worked.
+    /// On failure, everything is left as it was.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SolveError::GaveUp` when the search runs out of steps.
+    fn solve(&mut self) -> Result<bool, SolveError> {
+        if self.grid.len() == self.total {
+            return Ok(true);
+        }
+        let open = self.strip.iter().copied().filter(|cell| !self.grid.contains_key(cell))
+            .collect::<Vec<Cell>>();
+        if open.is_empty() {
+            return self.extend();
+        }
+        // The most constrained position of the strip; one that nothing
+        // fits means a dead end
+        let mut best: Option<(Cell, Vec<(usize, usize)>)> = None;
+        for cell in open {
+            let domain = self.domain(cell);
+            if domain.is_empty() {
+                return Ok(false);
+            }
+            if best.as_ref().is_none_or(|(_, b)| domain.len() < b.len()) {
+                best = Some((cell, domain));
+            }
+        }
+        let (cell, candidates) = match best {
+            Some(best) => best,
+            None => return Ok(false)
+        };
+        for (id, o) in candidates {
+            if self.steps == 0 {
+                return Err(SolveError::GaveUp);
+            }
+            self.steps -= 1;
+            self.grid.insert(cell, (id, o));
+            self.used.insert(id);
+            if self.solve()? {
+                return Ok(true);
+            }
+            self.grid.remove(&cell);
+            self.used.remove(&id);
+        }
+        Ok(false)
+    }
+
+    /// Add a row or column to the complete rectangle, on every side it
+    /// can grow in turn, and fill the rest.
+    ///
+    /// When growing on a side fails, no solution holds the tiles placed
+    /// so far along with any tile beyond that side, which is then closed
+    /// for the other sides tried: once both ends of a direction are, the
+    /// puzzle must be exactly as long as the rectangle in that direction.
+    fn extend(&mut self) -> Result<bool, SolveError> {
+        let (width, height) = self.dims();
+        let (top, left, bottom, right) = (self.top, self.left, self.bottom, self.right);
+        // The longest strips constrain the most
+        let mut sides = [(0, width), (2, width), (1, height), (3, height)];
+        sides.sort_by_key(|&(_, len)| std::cmp::Reverse(len));
+        let (before, closed) = (std::mem::take(&mut self.strip), self.closed);
+        for (side, _) in sides.iter().copied() {
+            let grown = if side % 2 == 0 { (width, height + 1) } else { (width + 1, height) };
+            if self.closed[side] || !self.fits(grown.0, grown.1) {
+                continue;
+            }
+            self.strip = match side {
+                0 => { self.top -= 1; (left..=right).map(|col| (top - 1, col)).collect() },
+                1 => { self.left -= 1; (top..=bottom).map(|row| (row, left - 1)).collect() },
+                2 => { self.bottom += 1; (left..=right).map(|col| (bottom + 1, col)).collect() },
+                _ => { self.right += 1; (top..=bottom).map(|row| (row, right + 1)).collect() }
+            };
+            if self.solve()? {
+                return Ok(true);
+            }
+            self.top = top;
+            self.left = left;
+            self.bottom = bottom;
+            self.right = right;
+            self.closed[side] = true;
+        }
+        self.strip = before;
+        self.closed = closed;
+        Ok(false)
+    }
+}
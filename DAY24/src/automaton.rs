@@ -0,0 +1,174 @@
+/// Rules of a life-like automaton on the hexagonal floor
+///
+/// A white tile turns black when its number of black neighbours is in
+/// `birth`, and a black tile stays black when its number of black
+/// neighbours is in `survival`. Every other tile ends up white.
+///
+/// Rules can be written in the usual `B.../S...` notation, where the digits
+/// after `B` and `S` are the neighbour counts of each set; the lobby of the
+/// puzzle follows `B2/S12`.
+#[derive(Clone,Copy,Debug,PartialEq,Eq)]
+struct HexRule {
+    birth: [bool; 7],
+    survival: [bool; 7]
+}
+
+/// An error met while building a [`HexRule`]
+#[derive(Debug,Clone,PartialEq,Eq)]
+enum RuleError {
+    /// A tile only has six neighbours
+    CountTooHigh(usize),
+    /// Births on zero neighbours would blacken the infinite white floor
+    BirthOnZero,
+    /// The rule string does not follow the `B.../S...` notation
+    Malformed(String)
+}
+
+impl HexRule {
+    /// Build a rule from the neighbour counts triggering births and
+    /// allowing survival
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`RuleError`] for counts above six, or births on zero.
+    fn new(birth: &[usize], survival: &[usize]) -> Result<Self, RuleError> {
+        let mut rule = HexRule { birth: [false; 7], survival: [false; 7] };
+        for (set, counts) in [(&mut rule.birth, birth), (&mut rule.survival, survival)] {
+            for &n in counts {
+                if n > 6 {
+                    return Err(RuleError::CountTooHigh(n));
+                }
+                set[n] = true;
+            }
+        }
+        if rule.birth[0] {
+            return Err(RuleError::BirthOnZero);
+        }
+        Ok(rule)
+    }
+
+    /// The rule of the lobby : born with 2, surviving with 1 or 2
+    fn lobby() -> Self {
+        HexRule::new(&[2], &[1, 2]).unwrap()
+    }
+
+    /// Whether a tile is black tomorrow
+    fn next_state(&self, black: bool, neighbours: usize) -> bool {
+        if black { self.survival[neighbours] } else { self.birth[neighbours] }
+    }
+}
+
+impl std::str::FromStr for HexRule {
+    type Err = RuleError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let malformed = || RuleError::Malformed(s.to_string());
+        let (b, sv) = s.trim().split_once('/').ok_or_else(malformed)?;
+        let digits = |part: &str, prefix: char| -> Result<Vec<usize>, RuleError> {
+            part.strip_prefix(prefix).ok_or_else(malformed)?
+                .chars()
+                .map(|c| c.to_digit(10).map(|d| d as usize).ok_or_else(malformed))
+                .collect()
+        };
+        HexRule::new(&digits(b, 'B')?, &digits(sv, 'S')?)
+    }
+}
+
+impl std::fmt::Display for HexRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let digits = |set: &[bool; 7]| (0..7)
+            .filter(|&n| set[n])
+            .map(|n| n.to_string())
+            .collect::<String>();
+        write!(f, "B{}/S{}", digits(&self.birth), digits(&self.survival))
+    }
+}
+
+/// Smallest rectangle of [doubled coordinates](Doubled) holding every
+/// black tile, bounds included
+#[allow(dead_code)]
+#[derive(Clone,Copy,Debug,PartialEq,Eq)]
+struct BoundingBox {
+    min_y: isize,
+    max_y: isize,
+    min_x: isize,
+    max_x: isize
+}
+
+/// What the floor looks like on a given day
+#[allow(dead_code)]
+#[derive(Clone,Copy,Debug,PartialEq,Eq)]
+struct Generation {
+    day: usize,
+    black: usize,
+    bounds: Option<BoundingBox>
+}
+
+/// A hexagonal floor evolving day after day under a [`HexRule`]
+///
+/// Only black tiles are stored; every day, the neighbours of black tiles
+/// are counted and the rule decides which tiles are black the next day.
+#[derive(Clone,Debug)]
+struct HexAutomaton {
+    rule: HexRule,
+    floor: HashSet<Doubled>,
+    day: usize
+}
+
+impl HexAutomaton {
+    /// Start an automaton on day 0 from a set of black tiles
+    fn new(floor: HashSet<Doubled>, rule: HexRule) -> Self {
+        HexAutomaton { rule, floor, day: 0 }
+    }
+
+    /// Move on to the next day
+    fn step(&mut self) {
+        let mut counts: HashMap<Doubled, usize> = HashMap::new();
+        for tile in &self.floor {
+            counts.entry(*tile).or_insert(0);
+            for n in tile.neighbours().iter() {
+                *counts.entry(*n).or_insert(0) += 1;
+            }
+        }
+        self.floor = counts.into_iter()
+            .filter(|&(tile, n)| self.rule.next_state(self.floor.contains(&tile), n))
+            .map(|(tile, _)| tile)
+            .collect();
+        self.day += 1;
+    }
+
+    /// The current state of the floor
+    #[allow(dead_code)]
+    fn generation(&self) -> Generation {
+        let bounds = self.floor.iter().fold(None, |acc: Option<BoundingBox>, t| Some(match acc {
+            None => BoundingBox { min_y: t.y, max_y: t.y, min_x: t.x, max_x: t.x },
+            Some(b) => BoundingBox {
+                min_y: b.min_y.min(t.y), max_y: b.max_y.max(t.y),
+                min_x: b.min_x.min(t.x), max_x: b.max_x.max(t.x)
+            }
+        }));
+        Generation { day: self.day, black: self.floor.len(), bounds }
+    }
+
+    /// Run for `days` days without keeping any history
+    fn advance(&mut self, days: usize) {
+        for _ in 0..days {
+            self.step();
+        }
+    }
+
+    /// Run for `days` days, returning the state of the floor on the
+    /// current day and after each of the following ones
+    ///
+    /// Every [`Generation`] costs a pass over the floor; use
+    /// [`HexAutomaton::advance`] when only the final floor matters.
+    #[allow(dead_code)]
+    fn run(&mut self, days: usize) -> Vec<Generation> {
+        let mut history = Vec::with_capacity(days + 1);
+        history.push(self.generation());
+        for _ in 0..days {
+            self.step();
+            history.push(self.generation());
+        }
+        history
+    }
+}
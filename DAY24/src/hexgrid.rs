@@ -0,0 +1,243 @@
+/// The six directions in which one can step on the hexagonal floor
+///
+/// Tiles are "pointy-topped" : they have neighbours due east and west, but
+/// none due north or south. The directions are listed clockwise, starting
+/// from the east, which is also the order of [`Direction::ALL`].
+#[derive(Clone,Copy,Debug,PartialEq,Eq,Hash)]
+enum Direction {
+    East,
+    SouthEast,
+    SouthWest,
+    West,
+    NorthWest,
+    NorthEast
+}
+
+impl Direction {
+    /// All six directions, clockwise from the east
+    const ALL: [Direction; 6] = [
+        Direction::East,
+        Direction::SouthEast,
+        Direction::SouthWest,
+        Direction::West,
+        Direction::NorthWest,
+        Direction::NorthEast
+    ];
+
+    /// The name of the direction, as found in the puzzle input
+    fn name(self) -> &'static str {
+        match self {
+            Direction::East      => "e",
+            Direction::SouthEast => "se",
+            Direction::SouthWest => "sw",
+            Direction::West      => "w",
+            Direction::NorthWest => "nw",
+            Direction::NorthEast => "ne"
+        }
+    }
+
+    /// The step taken by moving once in this direction, in cube coordinates
+    fn cube(self) -> Cube {
+        match self {
+            Direction::East      => Cube::new( 1,  0),
+            Direction::SouthEast => Cube::new( 0,  1),
+            Direction::SouthWest => Cube::new(-1,  1),
+            Direction::West      => Cube::new(-1,  0),
+            Direction::NorthWest => Cube::new( 0, -1),
+            Direction::NorthEast => Cube::new( 1, -1)
+        }
+    }
+}
+
+/// Doubled-width coordinates
+///
+/// This is the system described in
+/// [Hexagonal Coordinate System](self#hexagonal-coordinate-system) : moving
+/// east or west changes `x` by two, and moving diagonally changes both `x`
+/// and `y` by one. Only coordinates where `x + y` is even are valid.
+#[derive(Clone,Copy,Debug,PartialEq,Eq,Hash,PartialOrd,Ord)]
+struct Doubled {
+    y: isize,
+    x: isize
+}
+
+/// Axial coordinates
+///
+/// `q` grows towards the east and `r` towards the south-east. Every pair of
+/// integers is a valid tile, which makes this system the most compact one.
+#[derive(Clone,Copy,Debug,PartialEq,Eq,Hash,PartialOrd,Ord)]
+struct Axial {
+    q: isize,
+    r: isize
+}
+
+/// Cube coordinates
+///
+/// Axial coordinates with the redundant third axis `s = -q - r` made
+/// explicit. The three axes play symmetrical roles, which makes distances,
+/// rotations and interpolations straightforward.
+#[derive(Clone,Copy,Debug,PartialEq,Eq,Hash,PartialOrd,Ord)]
+struct Cube {
+    q: isize,
+    r: isize,
+    s: isize
+}
+
+impl From<Axial> for Cube {
+    fn from(a: Axial) -> Self {
+        Cube::new(a.q, a.r)
+    }
+}
+
+impl From<Cube> for Axial {
+    fn from(c: Cube) -> Self {
+        Axial { q: c.q, r: c.r }
+    }
+}
+
+impl From<Axial> for Doubled {
+    fn from(a: Axial) -> Self {
+        Doubled { y: a.r, x: 2 * a.q + a.r }
+    }
+}
+
+impl From<Doubled> for Axial {
+    fn from(d: Doubled) -> Self {
+        Axial { q: (d.x - d.y) / 2, r: d.y }
+    }
+}
+
+impl From<Cube> for Doubled {
+    fn from(c: Cube) -> Self {
+        Axial::from(c).into()
+    }
+}
+
+impl From<Doubled> for Cube {
+    fn from(d: Doubled) -> Self {
+        Axial::from(d).into()
+    }
+}
+
+impl std::ops::Add for Cube {
+    type Output = Cube;
+    fn add(self, other: Cube) -> Cube {
+        Cube::new(self.q + other.q, self.r + other.r)
+    }
+}
+
+impl std::ops::Sub for Cube {
+    type Output = Cube;
+    fn sub(self, other: Cube) -> Cube {
+        Cube::new(self.q - other.q, self.r - other.r)
+    }
+}
+
+impl Cube {
+    /// Build cube coordinates from their two free axes
+    fn new(q: isize, r: isize) -> Self {
+        Cube { q, r, s: -q - r }
+    }
+
+    /// The tile next to this one in direction `dir`
+    fn neighbour(self, dir: Direction) -> Cube {
+        self + dir.cube()
+    }
+
+    /// Number of steps needed to walk from this tile to `other`
+    fn distance(self, other: Cube) -> usize {
+        let d = self - other;
+        ((d.q.abs() + d.r.abs() + d.s.abs()) / 2) as usize
+    }
+
+    /// The tiles at exactly `radius` steps from this one
+    ///
+    /// The ring is walked clockwise, starting from the tile `radius` steps
+    /// to the north-west. A ring of radius `0` is the tile itself.
+    #[allow(dead_code)]
+    fn ring(self, radius: usize) -> Vec<Cube> {
+        if radius == 0 {
+            return vec![self];
+        }
+        let mut tile = (0..radius)
+            .fold(self, |t, _| t.neighbour(Direction::NorthWest));
+        let mut res = Vec::with_capacity(6 * radius);
+        for &dir in &Direction::ALL {
+            for _ in 0..radius {
+                res.push(tile);
+                tile = tile.neighbour(dir);
+            }
+        }
+        res
+    }
+
+    /// The tiles crossed by a straight line from this tile to `other`,
+    /// both ends included
+    #[allow(dead_code)]
+    fn line_to(self, other: Cube) -> Vec<Cube> {
+        let n = self.distance(other);
+        if n == 0 {
+            return vec![self];
+        }
+        // Nudge the ends slightly so that lines running exactly along
+        // edges always fall on the same side
+        let (aq, ar) = (self.q as f64 + 1e-6, self.r as f64 + 2e-6);
+        let (bq, br) = (other.q as f64 + 1e-6, other.r as f64 + 2e-6);
+        (0..=n).map(|i| {
+            let t = i as f64 / n as f64;
+            Cube::round(aq + (bq - aq) * t, ar + (br - ar) * t)
+        }).collect()
+    }
+
+    /// The tile containing fractional cube coordinates `(q, r, -q-r)`
+    fn round(q: f64, r: f64) -> Cube {
+        let s = -q - r;
+        let (rq, rr, rs) = (q.round(), r.round(), s.round());
+        let (dq, dr, ds) = ((rq - q).abs(), (rr - r).abs(), (rs - s).abs());
+        if dq > dr && dq > ds {
+            Cube::new((-rr - rs) as isize, rr as isize)
+        } else if dr > ds {
+            Cube::new(rq as isize, (-rq - rs) as isize)
+        } else {
+            Cube::new(rq as isize, rr as isize)
+        }
+    }
+}
+
+impl Doubled {
+    /// The tile next to this one in direction `dir`
+    fn neighbour(self, dir: Direction) -> Doubled {
+        Cube::from(self).neighbour(dir).into()
+    }
+
+    /// The six tiles around this one, clockwise from the east
+    fn neighbours(self) -> [Doubled; 6] {
+        Direction::ALL.map(|dir| self.neighbour(dir))
+    }
+
+    /// The tile reached by following `path` from this one
+    fn walk(self, path: &[Direction]) -> Doubled {
+        path.iter().fold(self, |t, &dir| t.neighbour(dir))
+    }
+}
+
+/// Shorten a path to one of the shortest paths reaching the same tile
+///
+/// Steps cancelling each other (`e` and `w`, `ne` and `sw`, ...) disappear,
+/// and detours are straightened (`ne` then `se` is just `e`). The result
+/// uses at most two, adjacent, directions, listed in the order of
+/// [`Direction::ALL`].
+fn simplify_path(path: &[Direction]) -> Vec<Direction> {
+    let origin = Cube::new(0, 0);
+    let target = path.iter().fold(origin, |t, &dir| t.neighbour(dir));
+    let mut res = Vec::with_capacity(origin.distance(target));
+    let mut tile = origin;
+    while tile != target {
+        let dir = *Direction::ALL.iter()
+            .find(|&&d| tile.neighbour(d).distance(target) < tile.distance(target))
+            .unwrap();
+        res.push(dir);
+        tile = tile.neighbour(dir);
+    }
+    res
+}
@@ -0,0 +1,135 @@
+/// An invalid sequence found while reading directions
+///
+/// Both `line` and `column` start at 1, and columns count characters, not
+/// bytes.
+#[derive(Debug,Clone,PartialEq,Eq)]
+struct LexError {
+    line: usize,
+    column: usize,
+    sequence: String
+}
+
+impl std::fmt::Display for LexError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}, column {}: invalid sequence {:?}",
+            self.line, self.column, self.sequence)
+    }
+}
+
+/// An error met while streaming directions from a reader
+#[derive(Debug)]
+enum DirectionError {
+    Io(std::io::Error),
+    Lex(LexError)
+}
+
+impl PartialEq for DirectionError {
+    /// Read errors are only compared by kind, `std::io::Error` not being
+    /// comparable itself
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (DirectionError::Io(a), DirectionError::Io(b)) => a.kind() == b.kind(),
+            (DirectionError::Lex(a), DirectionError::Lex(b)) => a == b,
+            _ => false
+        }
+    }
+}
+
+impl std::fmt::Display for DirectionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DirectionError::Io(e) => write!(f, "read error: {}", e),
+            DirectionError::Lex(e) => write!(f, "{}", e)
+        }
+    }
+}
+
+/// One line of instructions, and the tile it leads to
+#[derive(Debug,Clone,PartialEq,Eq)]
+struct TileLine {
+    line: usize,
+    path: Vec<Direction>,
+    tile: Doubled
+}
+
+/// Read one line of directions
+///
+/// Every direction is either `e`, `w`, or one of `n` and `s` immediately
+/// followed by one of `e` and `w`; no delimiter is needed because of that.
+/// Trailing whitespace (including the `\r` of CRLF line endings) is ignored.
+///
+/// # Arguments
+///
+///  - `text` : a `&str` holding the line, without its line feed
+///  - `line` : the number of the line, reported in errors
+///
+/// # Errors
+///
+/// Returns a [`LexError`] pointing at the first character that does not
+/// begin a direction, or at a lone `n`/`s` prefix.
+fn lex_line(text: &str, line: usize) -> Result<TileLine, LexError> {
+    let mut path = Vec::new();
+    let mut chars = text.trim_end().chars().enumerate().peekable();
+    while let Some((idx, c)) = chars.next() {
+        let dir = match c {
+            'e' => Some(Direction::East),
+            'w' => Some(Direction::West),
+            'n' | 's' => match chars.peek() {
+                Some(&(_, second)) => {
+                    let dir = match (c, second) {
+                        ('n', 'e') => Some(Direction::NorthEast),
+                        ('n', 'w') => Some(Direction::NorthWest),
+                        ('s', 'e') => Some(Direction::SouthEast),
+                        ('s', 'w') => Some(Direction::SouthWest),
+                        _ => None
+                    };
+                    if dir.is_some() {
+                        chars.next();
+                        dir
+                    } else {
+                        return Err(LexError {
+                            line, column: idx + 1, sequence: format!("{}{}", c, second)
+                        });
+                    }
+                },
+                None => None
+            },
+            _ => None
+        };
+        match dir {
+            Some(d) => path.push(d),
+            None => return Err(LexError { line, column: idx + 1, sequence: c.to_string() })
+        }
+    }
+    let tile = Doubled { y: 0, x: 0 }.walk(&path);
+    Ok(TileLine { line, path, tile })
+}
+
+/// Iterator over the instruction lines of a reader
+///
+/// Blank lines are skipped, but still counted for error reporting.
+struct TileLines<R: BufRead> {
+    lines: std::io::Lines<R>,
+    line: usize
+}
+
+impl<R: BufRead> TileLines<R> {
+    fn new(reader: R) -> Self {
+        TileLines { lines: reader.lines(), line: 0 }
+    }
+}
+
+impl<R: BufRead> Iterator for TileLines<R> {
+    type Item = Result<TileLine, DirectionError>;
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            self.line += 1;
+            match self.lines.next()? {
+                Err(e) => return Some(Err(DirectionError::Io(e))),
+                Ok(text) if text.trim().is_empty() => continue,
+                Ok(text) => return Some(lex_line(&text, self.line)
+                    .map_err(DirectionError::Lex))
+            }
+        }
+    }
+}
@@ -25,7 +25,7 @@
 //! Notice that this system has all of the qualities we want out of a coordinate system :
 //!  - The coordinate of every tile is *unique* (albeit with "gaps", i.e. invalid coordinates)
 //!  - A path that should loop is indeed *closed* (implying that repeating these movements will never
-//!  let us out of the valid positions)
+//!    let us out of the valid positions)
 //!
 //! This coordinate system is therefore adopted to represent the hexagonal grid in a cartesian
 //! fashion.
@@ -33,7 +33,11 @@
 
 use std::fs::File;
 use std::io::prelude::*;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+
+include!("hexgrid.rs");
+include!("lexer.rs");
+include!("automaton.rs");
 
 /// Read the day's input data from a file.
 ///
@@ -56,92 +60,43 @@ fn read_data(filepath: &str) -> std::io::Result<String> {
 ///
 /// # Arguments
 ///
-///  - `data` : a `&str` giving the steps to the various tiles
-///  
-///  The format used for `data` is exactly that of the raw input.
+///  - `reader` : a [`BufRead`] giving the steps to the various tiles
+///
+///  The format read from `reader` is exactly that of the raw input.
 ///
 /// # Return value
 ///
-/// Returns a `HashSet<isize,isize>` containing
+/// Returns a `Result<HashSet<Doubled>, DirectionError>` where `Ok` contains
 /// the coordinates for the tiles flipped to black.
 ///
-/// # Pre-parsing and delimiters
+/// # Errors
+///
+/// Returns the [`DirectionError`] of the first line that could not be read,
+/// or that contains something other than directions.
 ///
-/// In order to properly use the raw input, several modifications have to be
-/// made from the original string contents until it can finally be processed.
-/// 
-/// ## Input properties
+/// # Input properties
 ///
 /// The raw input has several very useful properties
 ///  - It is *unambiguous* : since we cannot move north and south directly, any `"s"` and `"n"` is
-///  necessarily followed by another cardinal direction. Since there are only six of those we can
-///  move towards, encountering `"e"` and `"w"` means that the next character begins another
-///  direction.
+///    necessarily followed by another cardinal direction. Since there are only six of those we can
+///    move towards, encountering `"e"` and `"w"` means that the next character begins another
+///    direction.
 ///  - All of the directions *end in either East or West* : following the point above, all of the
-///  direction instructions necessarily end with `"e"` or `"w"`; this will be useful to insert
-///  delimiters.
-///  - Identification only happens *when a line feed is encountered* : that it when coordinates are
-///  reset, but the input data will not finish with a line feed, so we are going to add one at the
-///  end.
-///
-/// ## Input parsing
-///
-/// As mentioned before, we need to add a delimiter in order to easily split our data without
-/// having to seek forward into the string (i.e. splitting in a way that lets us use simple iterators).
-/// First off, every direction ends with `"e"` or `"w"`. Moreover, line feeds should also be
-/// delimited since they represent instructions. Finally, a line feed must be added at the end to
-/// identify the final tile (this could be done outside of the iteration but this is cleaner).
-/// So we simply say
-/// ```rust
-/// fn initialize_hexgrid(data: &str) -> HashSet<(isize,isize)> {
-///     // insert delimiters
-///     let data = data.replace("\n", "\n,");
-///     let data = format!("{}\n", data);
-///     let data = data.replace("e", "e,");
-///     let data = data.replace("w", "w,");
-///     // ...
-/// }
-/// ```
-///
-/// Here are a couple reasons why this is arranged in this precise fashion :
-///  - Adding the final line feed after delimiting the previous line feeds means
-///  that we will not have to deal with a `","` at the end of the data (i.e. an empty
-///  string for the last item and one too many iteration).
-///  - Formatting the last line feed into the string before adding other delimiters
-///  means less data is processed by `format!`, saving a tiny bit of runtime.
-///  - Adding delimiters *after* the delimiters is way easier because we know the
-///  end of every single one of our items, and this prevents a blank entry in the
-///  beginning.
-fn initialize_hexgrid(data: &str) -> HashSet<(isize,isize)> {
-    // insert delimits
-    let data = data.replace("\n", "\n,");
-    let data = format!("{}\n", data);
-    let data = data.replace("e", "e,");
-    let data = data.replace("w", "w,");
-    // Remove potential simple loops
-    let mut x: isize = 0;
-    let mut y: isize = 0;
-    let mut flipped: HashSet<(isize,isize)> = HashSet::new();
-    for mov in data.split(',') {
-        // Do movement
-        match mov {
-            "nw"    => { x -= 1; y -= 1; },
-            "w"     => { x -= 2; },
-            "sw"    => { x -= 1; y += 1; },
-            "se"    => { x += 1; y += 1; },
-            "e"     => { x += 2; },
-            "ne"    => { x += 1; y -= 1; }
-            "\n"    => {
-                if !flipped.remove(&(y,x)) {
-                    flipped.insert((y,x));
-                }
-                x = 0;
-                y = 0;
-            },
-            _       => panic!("Weep")
+///    direction instructions necessarily end with `"e"` or `"w"`.
+///  - Identification only happens *at the end of a line* : that is when coordinates are
+///    reset.
+///
+/// This lets [`lex_line`] read every line left to right, looking at most one character ahead,
+/// without having to insert delimiters first.
+fn initialize_hexgrid<R: BufRead>(reader: R) -> Result<HashSet<Doubled>, DirectionError> {
+    let mut flipped: HashSet<Doubled> = HashSet::new();
+    for entry in TileLines::new(reader) {
+        let tile = entry?.tile;
+        if !flipped.remove(&tile) {
+            flipped.insert(tile);
         }
     }
-    flipped
+    Ok(flipped)
 }
 
 /// Solve Advent of Code Day 24 part 1
@@ -152,48 +107,20 @@ fn initialize_hexgrid(data: &str) -> HashSet<(isize,isize)> {
 ///
 /// # Returns
 ///
-/// Returns a `Result<usize,()>` where `Ok` contains the result.
+/// Returns a `Result<usize,DirectionError>` where `Ok` contains the result.
 ///
 /// # Errors
 ///
-/// There is no specific error type for this part, so `Err` always
-/// contains `()`.
-fn sol1(data: &str) -> Result<usize,()> {
-    Ok(initialize_hexgrid(data).len())
-}
-
-/// Get the immediate neighbours of given coordinates
-///
-/// Provided a set of two coordinates, give back a set of the
-/// coordinates of the tile's immediate neighbours.
-///
-/// See [Hexagonal Coordinate System](self#hexagonal-coordinate-system).
-///
-/// # Arguments
-///
-///  - `y` : a `isize` giving the coordinate along the `y` axis.
-///  - `x` : a `isize` giving the coordinate along the `x` axis.
-///
-/// # Return value
-///
-/// This function returns a `HashSet<(isize,isize)>` that contains coordinates of the
-/// immediate neighbours of the tile which coordinates are provided as arguments.
-fn immediate_neighbours(y: isize, x: isize) -> HashSet<(isize,isize)> {
-    [
-        (-1,-1), // NW
-        ( 0,-2), // W
-        ( 1,-1), // SW
-        ( 1, 1), // SE
-        ( 0, 2), // E
-        (-1, 1)  // NE
-    ].iter().map(|(dy,dx)| (dy+y,dx+x)).collect()
+/// Returns the error met while building the grid, see [`initialize_hexgrid`].
+fn sol1(data: &str) -> Result<usize,DirectionError> {
+    Ok(initialize_hexgrid(data.as_bytes())?.len())
 }
 
 /// Solve Advent of Code day 24 part 2
 ///
-/// A simple hexgrid is built (exactly as for [`sol1`]) and then
-/// a naive implementation of Conway's Game Of Life algorithm with
-/// custom parameters is applied a hundred times.
+/// A simple hexgrid is built (exactly as for [`sol1`]) and then handed
+/// to a [`HexAutomaton`] following the lobby's rules (see
+/// [`HexRule::lobby`]) for a hundred days.
 ///
 /// # Arguments
 ///
@@ -201,39 +128,40 @@ fn immediate_neighbours(y: isize, x: isize) -> HashSet<(isize,isize)> {
 ///
 /// # Return value
 ///
-/// This functions returns a `Result<usize,()>` where `Ok` contains the
+/// This functions returns a `Result<usize,DirectionError>` where `Ok` contains the
 /// final number of tiles flipped to black (i.e. the final number of
 /// entries in our data structure containing only the black tiles).
 ///
 /// # Errors
 ///
-/// There is no specific error type, so any `Err` returned contains `()`.
-fn sol2(data: &str) -> Result<usize,()> {
-    let mut hexgrid = initialize_hexgrid(data);
-    for _ in 1..=100 {
-        // Build a list of nodes to be updated
-        let tbu = hexgrid.iter().flat_map(|&(y,x)| {
-            let mut neighbour = immediate_neighbours(y,x);
-            neighbour.insert((y,x));
-            neighbour
-        }).collect::<HashSet<(isize,isize)>>();
-        // Build the next day
-        let mut next_day: HashSet<(isize,isize)> = HashSet::new();
-        for (yi,xi) in tbu {
-            // How many neighbouring black tiles?
-            let nbt = immediate_neighbours(yi,xi)
-                .iter().filter(|entry| hexgrid.contains(&entry)).count();
-            if nbt == 2 || (hexgrid.contains(&(yi,xi)) && nbt == 1) {
-                next_day.insert((yi,xi));
-            }
-        }
-        hexgrid = next_day;
-    }
-    Ok(hexgrid.len())
+/// Returns the error met while building the grid, see [`initialize_hexgrid`].
+fn sol2(data: &str) -> Result<usize,DirectionError> {
+    let mut lobby = HexAutomaton::new(initialize_hexgrid(data.as_bytes())?, HexRule::lobby());
+    lobby.advance(100);
+    Ok(lobby.floor.len())
 }
 
 #[doc(hidden)]
 fn main() {
+    // `day24 <file>` (or `-` for stdin) lists the tile reached by each line
+    if let Some(path) = std::env::args().nth(1) {
+        let reader: Box<dyn BufRead> = if path == "-" {
+            Box::new(std::io::BufReader::new(std::io::stdin()))
+        } else {
+            match File::open(&path) {
+                Ok(file) => Box::new(std::io::BufReader::new(file)),
+                Err(e) => { eprintln!("{}: {}", path, e); return; }
+            }
+        };
+        for entry in TileLines::new(reader) {
+            match entry {
+                Ok(tl) => println!("{}\t{}\t{}\t{}", tl.line, tl.tile.y, tl.tile.x,
+                    simplify_path(&tl.path).iter().map(|d| d.name()).collect::<String>()),
+                Err(e) => eprintln!("{}: {}", path, e)
+            }
+        }
+        return;
+    }
     if let Ok(data) = read_data("input") {
         println!("{:?}", sol1(&data));
         println!("{:?}", sol2(&data));
@@ -254,4 +182,94 @@ mod test {
         let data = read_data("test_input").unwrap();
         assert_eq!(sol2(&data), Ok(2208))
     }
+    #[test]
+    fn lobby_history() {
+        let data = read_data("test_input").unwrap();
+        let mut lobby = HexAutomaton::new(initialize_hexgrid(data.as_bytes()).unwrap(), HexRule::lobby());
+        let series = lobby.run(10).iter().map(|g| g.black).collect::<Vec<usize>>();
+        assert_eq!(series, vec![10, 15, 12, 25, 14, 23, 28, 41, 37, 49, 37]);
+        let bounds = lobby.generation().bounds.unwrap();
+        assert!(bounds.min_y <= bounds.max_y && bounds.min_x <= bounds.max_x);
+        assert!(lobby.floor.iter().all(|t| (bounds.min_y..=bounds.max_y).contains(&t.y)
+            && (bounds.min_x..=bounds.max_x).contains(&t.x)));
+    }
+    #[test]
+    fn rule_notation() {
+        assert_eq!("B2/S12".parse::<HexRule>(), Ok(HexRule::lobby()));
+        assert_eq!(HexRule::new(&[3, 4], &[]).unwrap().to_string(), "B34/S");
+        assert_eq!("B0/S1".parse::<HexRule>(), Err(RuleError::BirthOnZero));
+        assert_eq!("B7/S1".parse::<HexRule>(), Err(RuleError::CountTooHigh(7)));
+        assert!(matches!("B2S1".parse::<HexRule>(), Err(RuleError::Malformed(_))));
+        // Nothing ever survives nor is born
+        let floor = [Doubled { y: 0, x: 0 }].iter().copied().collect();
+        let mut empty = HexAutomaton::new(floor, "B/S".parse().unwrap());
+        assert_eq!(empty.run(1)[1], Generation { day: 1, black: 0, bounds: None });
+    }
+    #[test]
+    fn lexer_errors() {
+        assert_eq!(lex_line("esew", 1).map(|tl| tl.tile), Ok(Doubled { y: 1, x: 1 }));
+        assert_eq!(lex_line("nwwswee \r", 2).map(|tl| tl.path.len()), Ok(5));
+        assert_eq!(lex_line("eenxw", 3),
+            Err(LexError { line: 3, column: 3, sequence: "nx".to_string() }));
+        assert_eq!(lex_line("wq", 4),
+            Err(LexError { line: 4, column: 2, sequence: "q".to_string() }));
+        assert_eq!(lex_line("ees", 5),
+            Err(LexError { line: 5, column: 3, sequence: "s".to_string() }));
+        assert_eq!(sol1("e\nnew\nsez"), Err(DirectionError::Lex(
+            LexError { line: 3, column: 3, sequence: "z".to_string() })));
+    }
+    #[test]
+    fn streamed_lines() {
+        let input = "esew\r\n\r\nnwwswee\r\nne?\n";
+        let lines = TileLines::new(input.as_bytes()).collect::<Vec<_>>();
+        assert_eq!(lines.len(), 3);
+        assert!(matches!(&lines[0], Ok(TileLine { line: 1, tile: Doubled { y: 1, x: 1 }, .. })));
+        assert!(matches!(&lines[1], Ok(TileLine { line: 3, tile: Doubled { y: 0, x: 0 }, .. })));
+        assert!(matches!(&lines[2],
+            Err(DirectionError::Lex(LexError { line: 4, column: 3, .. }))));
+    }
+    #[test]
+    fn coordinate_conversions() {
+        let tile = Doubled { y: -3, x: 5 };
+        let axial = Axial::from(tile);
+        assert_eq!(axial, Axial { q: 4, r: -3 });
+        let cube = Cube::from(axial);
+        assert_eq!(cube.q + cube.r + cube.s, 0);
+        assert_eq!(Doubled::from(cube), tile);
+        assert_eq!(tile.neighbour(Direction::East), Doubled { y: -3, x: 7 });
+        assert_eq!(tile.neighbour(Direction::SouthWest), Doubled { y: -2, x: 4 });
+    }
+    #[test]
+    fn distances_and_rings() {
+        let origin = Cube::new(0, 0);
+        for radius in 0..5 {
+            let ring = origin.ring(radius);
+            assert_eq!(ring.len(), if radius == 0 { 1 } else { 6 * radius });
+            assert!(ring.iter().all(|t| t.distance(origin) == radius));
+            assert_eq!(ring.iter().collect::<HashSet<_>>().len(), ring.len());
+        }
+        assert_eq!(origin.distance(Doubled { y: 3, x: -1 }.into()), 3);
+        assert_eq!(origin.distance(Doubled { y: 1, x: 7 }.into()), 4);
+    }
+    #[test]
+    fn straight_lines() {
+        let a = Cube::new(-2, 0);
+        let b = Cube::new(3, -2);
+        let line = a.line_to(b);
+        assert_eq!(line.len(), a.distance(b) + 1);
+        assert_eq!((line[0], line[line.len() - 1]), (a, b));
+        assert!(line.windows(2).all(|w| w[0].distance(w[1]) == 1));
+    }
+    #[test]
+    fn path_simplification() {
+        use Direction::*;
+        assert_eq!(simplify_path(&[East, West, NorthEast, SouthWest]), vec![]);
+        assert_eq!(simplify_path(&[NorthEast, SouthEast]), vec![East]);
+        assert_eq!(simplify_path(&[NorthWest, West, SouthWest]), vec![West, West]);
+        let path = [NorthEast, NorthEast, East, SouthEast, NorthWest, West, NorthEast];
+        let short = simplify_path(&path);
+        let origin = Doubled { y: 0, x: 0 };
+        assert_eq!(origin.walk(&short), origin.walk(&path));
+        assert_eq!(short.len(), Cube::from(origin).distance(origin.walk(&path).into()));
+    }
 }